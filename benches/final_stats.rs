@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use crabtype::{
+    analysis::{FinalStats, TestMode},
+    words::WordList,
+};
+
+fn final_stats(c: &mut Criterion) {
+    let correct: Vec<String> = WordList::built_in().words.iter().cycle().take(10_000).cloned().collect();
+    // every fifth word has a typo, every seventh is cut short
+    let input: Vec<String> = correct
+        .iter()
+        .enumerate()
+        .map(|(i, word)| match (i % 5, i % 7) {
+            (0, _) => format!("{word}x"),
+            (_, 0) => word[..word.len() / 2].to_string(),
+            _ => word.clone(),
+        })
+        .collect();
+    c.bench_function("final stats of 10k words", |b| {
+        b.iter(|| FinalStats::calculate(black_box(&input), black_box(&correct), Duration::from_secs(600), &TestMode::Duration(Duration::from_secs(600))))
+    });
+}
+
+criterion_group!(benches, final_stats);
+criterion_main!(benches);
@@ -0,0 +1,23 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use crabtype::{analysis::word_difference, words::WordList};
+
+/// The typing widget lives in the binary, so this measures the part of drawing
+/// a frame that's in the library: comparing every typed word with its word.
+fn word_differences(c: &mut Criterion) {
+    // 40 half typed words, like a frame in the middle of a test
+    let words = &WordList::built_in().words[..40];
+    let written: Vec<&str> = words.iter().map(|word| &word[..word.len() / 2]).collect();
+    c.bench_function("word differences of a frame", |b| {
+        b.iter(|| {
+            words
+                .iter()
+                .zip(&written)
+                .map(|(word, input)| word_difference(black_box(word), black_box(input)).count())
+                .sum::<usize>()
+        })
+    });
+}
+
+criterion_group!(benches, word_differences);
+criterion_main!(benches);
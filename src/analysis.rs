@@ -0,0 +1,762 @@
+//! Pure calculations behind the results screen.
+
+use std::{fmt, str::FromStr, time::Duration};
+
+use itertools::{EitherOrBoth, Itertools};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator, IntoStaticStr};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A single recorded keystroke during a test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyStrokeKind {
+    Correct(char),
+    Incorrect(char),
+    /// the right letter with the wrong case, only recorded when case mismatches are lenient
+    WrongCase(char),
+    ///amount of extra letters in the word before, when n < 0, skipped letters
+    Space(i32),
+    /// backspace or Ctrl+W, with the index of the word that was corrected
+    Backspace(usize),
+}
+
+/// How a test ends: after a fixed time, a fixed amount of words or a fixed
+/// amount of characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TestMode {
+    Duration(Duration),
+    Words(usize),
+    /// typed characters, the spaces between words included
+    Chars(usize),
+}
+
+impl fmt::Display for TestMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TestMode::Duration(d) => write!(f, "time:{}", d.as_secs()),
+            TestMode::Words(words) => write!(f, "words:{words}"),
+            TestMode::Chars(chars) => write!(f, "chars:{chars}"),
+        }
+    }
+}
+
+/// Parses the same format `Display` produces, e.g. `time:30`, `words:50` or `chars:200`.
+impl FromStr for TestMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_amount = |amount: &str| {
+            amount
+                .parse()
+                .map_err(|_| format!("invalid amount `{amount}` in `{s}`"))
+        };
+        match s.split_once(':') {
+            Some(("time", secs)) => Ok(TestMode::Duration(Duration::from_secs(parse_amount(secs)?))),
+            Some(("words", words)) => Ok(TestMode::Words(parse_amount(words)? as usize)),
+            Some(("chars", chars)) => Ok(TestMode::Chars(parse_amount(chars)? as usize)),
+            _ => Err(format!("expected `time:<seconds>`, `words:<amount>` or `chars:<amount>`, got `{s}`")),
+        }
+    }
+}
+
+/// Results computed from the final text, see [`FinalStats::calculate`].
+#[derive(Serialize, PartialEq, Clone, Debug)]
+pub struct FinalStats {
+    pub wpm: f64,     // amount of characters in fully correct words + spaces normalized
+    pub raw_wpm: f64, // wpm with incorrect words' characters
+    pub correct: u32,
+    /// wrong letters, not counting the ones that are only in the wrong case
+    pub incorrect: u32,
+    /// right letters in the wrong case
+    pub case_errors: u32,
+    pub extra: u32,
+    pub missed: u32,
+}
+
+/// Keystroke based accuracy, see [`calculate_accuracy`].
+#[derive(Serialize, PartialEq, Clone, Debug)]
+pub struct Accuracy {
+    pub correct: u32,
+    pub incorrect: u32,
+    /// `correct / (correct + incorrect)`, 0 when nothing was typed
+    pub accuracy: f64,
+}
+
+impl Default for FinalStats {
+    fn default() -> Self {
+        Self {
+            wpm: 0.0,
+            raw_wpm: 0.0,
+            correct: 0,
+            incorrect: 0,
+            case_errors: 0,
+            extra: 0,
+            missed: 0,
+        }
+    }
+}
+
+impl FinalStats {
+    /// Compares the typed words against the target words. Words past the end
+    /// of either slice are ignored.
+    pub fn calculate(
+        inputted_words: &[String],
+        correct_words: &[String],
+        test_duration: Duration, //for normalizing wpm
+        mode: &TestMode,
+    ) -> Self {
+        // a test can end mid word, when time runs out or it's ended early, and only the
+        // typed part of that word counts. A finished words test ends after its last space.
+        let partial_index = inputted_words.len().checked_sub(1).filter(|&last| match mode {
+            TestMode::Words(words) => last < *words,
+            TestMode::Duration(_) | TestMode::Chars(_) => true,
+        });
+        let mut result = inputted_words
+            .iter()
+            .zip(correct_words.iter())
+            .enumerate()
+            .fold(Self::default(), |mut acc, (i, (input, correct))| {
+                // one pass over every word, this runs over the whole test
+                let diff = DiffCounts::new(correct, input);
+                let wrong = diff.incorrect + diff.case_errors;
+                let input_len = diff.correct + wrong + diff.extra;
+                let is_partial = Some(i) == partial_index;
+                if wrong + diff.extra + diff.missed == 0 {
+                    acc.wpm += input_len as f64 + 1.0
+                } else if is_partial && wrong + diff.extra == 0 {
+                    acc.wpm += input_len as f64;
+                    acc.raw_wpm -= 1.0;
+                }
+                acc.raw_wpm += input_len as f64 + 1.0;
+                acc.correct += diff.correct;
+                acc.incorrect += diff.incorrect;
+                acc.case_errors += diff.case_errors;
+                acc.extra += diff.extra;
+                // the rest of the word wasn't missed, the test ended before it
+                if !is_partial {
+                    acc.missed += diff.missed;
+                }
+                acc
+            });
+        result.wpm = normalize_wpm(result.wpm, test_duration.as_secs_f64());
+        result.raw_wpm = normalize_wpm(result.raw_wpm, test_duration.as_secs_f64());
+        result
+    }
+}
+
+/// How many letters of each [`CharDiffKind`] there are in a word.
+#[derive(Default)]
+struct DiffCounts {
+    correct: u32,
+    incorrect: u32,
+    case_errors: u32,
+    extra: u32,
+    missed: u32,
+}
+
+impl DiffCounts {
+    fn new(correct_word: &str, input: &str) -> Self {
+        let mut counts = Self::default();
+        if correct_word.is_ascii() && input.is_ascii() {
+            // every ascii byte is a letter of its own, no need to look for graphemes
+            let common = correct_word.len().min(input.len());
+            for (c, i) in correct_word.bytes().zip(input.bytes()) {
+                match (c == i, c.eq_ignore_ascii_case(&i)) {
+                    (true, _) => counts.correct += 1,
+                    (false, true) => counts.case_errors += 1,
+                    (false, false) => counts.incorrect += 1,
+                }
+            }
+            counts.extra = (input.len() - common) as u32;
+            counts.missed = (correct_word.len() - common) as u32;
+            return counts;
+        }
+        for d in word_difference(correct_word, input) {
+            match d {
+                CharDiffKind::Correct => counts.correct += 1,
+                CharDiffKind::Incorrect => counts.incorrect += 1,
+                CharDiffKind::CaseError => counts.case_errors += 1,
+                CharDiffKind::Extra => counts.extra += 1,
+                CharDiffKind::Missed => counts.missed += 1,
+            }
+        }
+        counts
+    }
+}
+
+/// Amount of letters in `s`, a letter followed by combining accents counts as one.
+pub fn grapheme_len(s: &str) -> usize {
+    s.graphemes(true).count()
+}
+
+/// The first `n` letters of `s`, or all of it when it's shorter.
+pub fn grapheme_prefix(s: &str, n: usize) -> &str {
+    s.grapheme_indices(true).nth(n).map_or(s, |(i, _)| &s[..i])
+}
+
+/// Whether two letters are the same, no matter if an accent was typed
+/// precomposed or with a dead key as a combining character.
+pub fn same_grapheme(a: &str, b: &str) -> bool {
+    a == b || a.nfc().eq(b.nfc())
+}
+
+/// Whether `input` is exactly `correct_word`, letter by letter.
+pub fn words_match(correct_word: &str, input: &str) -> bool {
+    word_difference(correct_word, input).all(|d| d == CharDiffKind::Correct)
+}
+
+/// Converts an amount of characters typed in `time` seconds to words per minute,
+/// counting 5 characters as one word.
+pub fn normalize_wpm(char_amount: f64, time: f64) -> f64 {
+    normalize_speed(char_amount, time, SpeedUnit::Wpm)
+}
+
+/// Converts an amount of characters or keystrokes typed in `time` seconds to
+/// a speed per minute in `unit`.
+pub fn normalize_speed(amount: f64, time: f64, unit: SpeedUnit) -> f64 {
+    if time <= 0.0 {
+        return 0.0;
+    }
+    amount / unit.divisor() * (60.0 / time)
+}
+
+/// The unit speeds are shown in.
+#[derive(EnumString, EnumIter, Display, IntoStaticStr, clap::ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum SpeedUnit {
+    /// words per minute, 5 characters each
+    #[default]
+    Wpm,
+    /// characters per minute
+    Cpm,
+}
+
+impl SpeedUnit {
+    /// Characters counted as one.
+    pub fn divisor(self) -> f64 {
+        match self {
+            SpeedUnit::Wpm => 5.0,
+            SpeedUnit::Cpm => 1.0,
+        }
+    }
+
+    /// The same speed as `wpm` in this unit.
+    pub fn from_wpm(self, wpm: f64) -> f64 {
+        wpm * SpeedUnit::Wpm.divisor() / self.divisor()
+    }
+
+    /// The unit after this one, wrapping around.
+    pub fn next(self) -> Self {
+        SpeedUnit::iter().cycle().skip_while(|&unit| unit != self).nth(1).unwrap()
+    }
+}
+
+#[allow(unused)]
+#[derive(PartialEq, Clone, Debug)]
+pub enum CharDiffKind {
+    Correct,
+    Incorrect,
+    /// the right letter in the wrong case
+    CaseError,
+    Extra,
+    Missed,
+}
+
+/// Classifies every letter of `input` against `correct_word`, position by position.
+pub fn word_difference<'a>(
+    correct_word: &'a str,
+    input: &'a str,
+) -> impl Iterator<Item = CharDiffKind> + 'a {
+    correct_word
+        .graphemes(true)
+        .zip_longest(input.graphemes(true))
+        .map(|e| match e {
+            EitherOrBoth::Left(_) => CharDiffKind::Missed,
+            EitherOrBoth::Right(_) => CharDiffKind::Extra,
+            EitherOrBoth::Both(c, i) => {
+                if same_grapheme(c, i) {
+                    CharDiffKind::Correct
+                } else if is_case_mismatch(c, i) {
+                    CharDiffKind::CaseError
+                } else {
+                    CharDiffKind::Incorrect
+                }
+            }
+        })
+}
+
+/// Whether `typed` is `expected` in a different case, e.g. Shift or CapsLock was held by accident.
+pub fn is_case_mismatch(expected: &str, typed: &str) -> bool {
+    let lowercase = |s: &str| s.nfc().flat_map(char::to_lowercase).collect::<String>();
+    !same_grapheme(expected, typed) && lowercase(expected) == lowercase(typed)
+}
+
+/// Accuracy is based on the keystrokes, so mistakes that were fixed with backspace still count.
+/// Without corrections it matches `FinalStats`: correct / (correct + incorrect + case_errors + extra + missed).
+pub fn calculate_accuracy(key_strokes: &[(Duration, KeyStrokeKind)]) -> Accuracy {
+    let mut correct = 0;
+    let mut incorrect = 0;
+    for (_, ks) in key_strokes.iter() {
+        match ks {
+            // a letter in the wrong case still finds the right key
+            KeyStrokeKind::Correct(_) | KeyStrokeKind::WrongCase(_) => correct += 1,
+            KeyStrokeKind::Incorrect(_) => incorrect += 1,
+            // extra letters were already counted as incorrect keystrokes
+            KeyStrokeKind::Space(i) if *i < 0 => incorrect += i.unsigned_abs(),
+            // the mistake a backspace fixes was already counted
+            _ => (),
+        }
+    }
+    Accuracy {
+        correct,
+        incorrect,
+        accuracy: match correct + incorrect {
+            0 => 0.0,
+            total => correct as f64 / total as f64,
+        },
+    }
+}
+
+/// How many times backspace or Ctrl+W was pressed.
+pub fn count_backspaces(key_strokes: &[(Duration, KeyStrokeKind)]) -> u32 {
+    key_strokes
+        .iter()
+        .filter(|(_, ks)| matches!(ks, KeyStrokeKind::Backspace(_)))
+        .count() as u32
+}
+
+/// What the accuracy of a run is calculated from.
+#[derive(EnumString, Display, clap::ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum AccuracyFormula {
+    /// every keystroke, mistakes fixed with backspace and letters skipped with space count
+    #[default]
+    Keystrokes,
+    /// only the keys that were pressed, skipped letters don't count
+    Typed,
+    /// the final text like monkeytype's actual accuracy, fixed mistakes don't count
+    /// but extra and missed letters do
+    Text,
+}
+
+/// The accuracy of a run with any [`AccuracyFormula`], `Keystrokes` is [`calculate_accuracy`].
+pub fn calculate_accuracy_with(
+    formula: AccuracyFormula,
+    key_strokes: &[(Duration, KeyStrokeKind)],
+    final_stats: &FinalStats,
+) -> Accuracy {
+    let (correct, incorrect) = match formula {
+        AccuracyFormula::Keystrokes => return calculate_accuracy(key_strokes),
+        AccuracyFormula::Typed => {
+            let accuracy = calculate_accuracy(key_strokes);
+            let skipped: u32 = key_strokes
+                .iter()
+                .map(|(_, ks)| match ks {
+                    KeyStrokeKind::Space(i) if *i < 0 => i.unsigned_abs(),
+                    _ => 0,
+                })
+                .sum();
+            (accuracy.correct, accuracy.incorrect - skipped)
+        }
+        AccuracyFormula::Text => (
+            final_stats.correct,
+            final_stats.incorrect + final_stats.case_errors + final_stats.extra + final_stats.missed,
+        ),
+    };
+    Accuracy {
+        correct,
+        incorrect,
+        accuracy: match correct + incorrect {
+            0 => 0.0,
+            total => correct as f64 / total as f64,
+        },
+    }
+}
+
+/// How evenly the keys of a run were pressed.
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum Rhythm {
+    /// about the same time between every key
+    Steady,
+    /// quick runs of keys with long pauses in between
+    Bursty,
+}
+
+/// Fewest gaps between keystrokes to tell the rhythm of a run from.
+const RHYTHM_MIN_GAPS: usize = 10;
+
+/// Gaps varying more than this relative to their mean make a run bursty.
+const BURSTY_VARIATION: f64 = 1.0;
+
+/// The rhythm of a run from the coefficient of variation of the gaps between
+/// its keystrokes, `None` when there are too few keystrokes to tell.
+pub fn typing_rhythm(key_strokes: &[(Duration, KeyStrokeKind)]) -> Option<Rhythm> {
+    let gaps = key_strokes
+        .iter()
+        .tuple_windows()
+        .map(|((before, _), (after, _))| after.saturating_sub(*before).as_secs_f64())
+        .collect_vec();
+    if gaps.len() < RHYTHM_MIN_GAPS {
+        return None;
+    }
+    let mean = gaps.iter().sum::<f64>() / gaps.len() as f64;
+    if mean <= 0.0 {
+        return None;
+    }
+    let variance = gaps.iter().map(|gap| (gap - mean).powi(2)).sum::<f64>() / gaps.len() as f64;
+    Some(match variance.sqrt() / mean > BURSTY_VARIATION {
+        true => Rhythm::Bursty,
+        false => Rhythm::Steady,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn word_dif_extra() {
+        use CharDiffKind::*;
+        assert!(word_difference("aabbc", "ahhbcaa")
+            .eq([Correct, Incorrect, Incorrect, Correct, Correct, Extra, Extra]))
+    }
+    #[test]
+    fn word_dif_missed() {
+        use CharDiffKind::*;
+        assert!(word_difference("bbbdas", "bbb")
+            .eq([Correct, Correct, Correct, Missed, Missed, Missed]))
+    }
+    #[test]
+    fn final_stats_empty() {
+        let stats = FinalStats::calculate(
+            &[],
+            &[],
+            Duration::from_secs(60),
+            &TestMode::Duration(Duration::from_secs(60)),
+        );
+        assert_eq!(stats, FinalStats::default())
+    }
+    #[test]
+    fn final_stats_all_correct() {
+        let input = ["dac", "b"].map(String::from);
+        let correct = ["dac", "bb"].map(String::from);
+        let stats = FinalStats::calculate(
+            &input,
+            &correct,
+            Duration::from_secs(
+                12, /* 12 to make char amount match wpm due to how normalize_wpm() works : x/5 * (60/12) = x  */
+            ),
+            &TestMode::Duration(Duration::from_secs(12)),
+        );
+        assert_eq!(
+            stats,
+            FinalStats {
+                wpm: 5.0, // 4 chars + 1 space
+                raw_wpm: 5.0,
+                correct: 4,
+                extra: 0,
+                incorrect: 0,
+                case_errors: 0,
+                missed: 0,
+            }
+        )
+    }
+    #[test]
+    fn final_stats_errors() {
+        let input = ["bbc", "bda", "cdq", "a"].map(String::from);
+        let correct = ["dac", "bb", "cd", "aaa"].map(String::from);
+        let stats = FinalStats::calculate(
+            &input,
+            &correct,
+            Duration::from_secs(12),
+            &TestMode::Duration(Duration::from_secs(12)),
+        );
+        assert_eq!(
+            stats,
+            FinalStats {
+                wpm: 1.0,
+                raw_wpm: 13.0,
+                correct: 5,
+                extra: 2,
+                incorrect: 3,
+                case_errors: 0,
+                missed: 0,
+            }
+        )
+    }
+    #[test]
+    fn final_stats_missed() {
+        let input = ["bb", "b", "ha", "b"].map(String::from);
+        let correct = ["bbaa", "baaa", "haaa", "b"].map(String::from);
+        let stats = FinalStats::calculate(
+            &input,
+            &correct,
+            Duration::from_secs(12),
+            &TestMode::Duration(Duration::from_secs(12)),
+        );
+        assert_eq!(
+            stats,
+            FinalStats {
+                wpm: 2.0,
+                raw_wpm: 10.0,
+                correct: 6,
+                extra: 0,
+                incorrect: 0,
+                case_errors: 0,
+                missed: 7,
+            }
+        )
+    }
+    #[test]
+    fn final_stats_duration() {
+        let input = ["aaaa", "aaaa", "aaaa", "aaaa"].map(String::from);
+        let correct = ["aaaa", "aaaa", "aaaa", "aaaa"].map(String::from);
+        let stats = FinalStats::calculate(
+            &input,
+            &correct,
+            Duration::from_secs(60),
+            &TestMode::Duration(Duration::from_secs(60)),
+        );
+        assert_eq!(
+            stats,
+            FinalStats {
+                wpm: 20.0 / 5.0, // 4 chars + 1 space
+                raw_wpm: 20.0 / 5.0,
+                correct: 16,
+                extra: 0,
+                incorrect: 0,
+                case_errors: 0,
+                missed: 0,
+            }
+        )
+    }
+    #[test]
+    fn final_stats_words_mode() {
+        // the test ends on the last space, so written words has a trailing empty word
+        let input = ["dac", "b", ""].map(String::from);
+        let correct = ["dac", "bb"].map(String::from);
+        let stats = FinalStats::calculate(
+            &input,
+            &correct,
+            Duration::from_secs(12),
+            &TestMode::Words(2),
+        );
+        assert_eq!(
+            stats,
+            FinalStats {
+                wpm: 4.0, // the last word isn't finished, so only "dac" + space count
+                raw_wpm: 6.0,
+                correct: 4,
+                extra: 0,
+                incorrect: 0,
+                case_errors: 0,
+                missed: 1,
+            }
+        )
+    }
+    #[test]
+    fn final_stats_non_ascii() {
+        let input = ["café", "ñ"].map(String::from);
+        let correct = ["café", "ñu"].map(String::from);
+        let stats = FinalStats::calculate(
+            &input,
+            &correct,
+            Duration::from_secs(12),
+            &TestMode::Duration(Duration::from_secs(12)),
+        );
+        assert_eq!(
+            stats,
+            FinalStats {
+                wpm: 6.0, // counted in chars, not bytes
+                raw_wpm: 6.0,
+                correct: 5,
+                extra: 0,
+                incorrect: 0,
+                case_errors: 0,
+                missed: 0,
+            }
+        );
+        assert_eq!(grapheme_prefix("ñu", 1), "ñ");
+        assert_eq!(grapheme_prefix("ñu", 5), "ñu");
+    }
+    #[test]
+    fn combining_accent() {
+        use CharDiffKind::*;
+        // "é" typed with a dead key vs the precomposed letter
+        assert!(word_difference("caf\u{e9}", "cafe\u{301}").eq([Correct, Correct, Correct, Correct]));
+        assert!(word_difference("caf\u{e9}", "cafe").eq([Correct, Correct, Correct, Incorrect]));
+        assert!(words_match("caf\u{e9}", "cafe\u{301}"));
+        assert_eq!(grapheme_len("cafe\u{301}"), 4);
+    }
+    #[test]
+    fn emoji_graphemes() {
+        use CharDiffKind::*;
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        assert!(word_difference(family, family).eq([Correct]));
+        let partial = "\u{1f468}\u{200d}\u{1f469}";
+        assert!(word_difference(&format!("{family}a"), &format!("{partial}a")).eq([Incorrect, Correct]));
+        assert!(word_difference(&format!("{family}ab"), partial).eq([Incorrect, Missed, Missed]));
+    }
+    #[test]
+    fn final_stats_ended_early() {
+        // a words test ended in the middle of the second word
+        let input = ["dac", "b"].map(String::from);
+        let correct = ["dac", "bb", "cd"].map(String::from);
+        let stats = FinalStats::calculate(&input, &correct, Duration::from_secs(12), &TestMode::Words(3));
+        assert_eq!(
+            stats,
+            FinalStats {
+                wpm: 5.0,
+                raw_wpm: 5.0,
+                correct: 4,
+                extra: 0,
+                incorrect: 0,
+                case_errors: 0,
+                missed: 0,
+            }
+        )
+    }
+    #[test]
+    fn ascii_diff_counts_match_graphemes() {
+        for (correct, input) in [("abc", "abc"), ("abc", "axcde"), ("abcd", "ab"), ("", "ab"), ("ab", ""), ("Ab", "aB?")] {
+            let fast = DiffCounts::new(correct, input);
+            let mut slow = DiffCounts::default();
+            for d in word_difference(correct, input) {
+                match d {
+                    CharDiffKind::Correct => slow.correct += 1,
+                    CharDiffKind::Incorrect => slow.incorrect += 1,
+                    CharDiffKind::CaseError => slow.case_errors += 1,
+                    CharDiffKind::Extra => slow.extra += 1,
+                    CharDiffKind::Missed => slow.missed += 1,
+                }
+            }
+            assert_eq!(
+                (fast.correct, fast.incorrect, fast.case_errors, fast.extra, fast.missed),
+                (slow.correct, slow.incorrect, slow.case_errors, slow.extra, slow.missed),
+                "{correct} vs {input}"
+            );
+        }
+    }
+    #[test]
+    fn accuracy_matches_final_stats() {
+        use KeyStrokeKind::*;
+        let key_strokes = [
+            Correct('a'),
+            Correct('b'),
+            Correct('c'),
+            Incorrect('x'),
+            Space(1),
+            Correct('a'),
+            Space(-3),
+            Correct('a'),
+        ]
+        .map(|ks| (Duration::ZERO, ks));
+        let input = ["abcx", "a", "a"].map(String::from);
+        let correct = ["abc", "abcd", "ab"].map(String::from);
+        let stats = FinalStats::calculate(
+            &input,
+            &correct,
+            Duration::from_secs(12),
+            &TestMode::Duration(Duration::from_secs(12)),
+        );
+        let total = stats.correct + stats.incorrect + stats.extra + stats.missed;
+        let accuracy = calculate_accuracy(&key_strokes);
+        assert_eq!(accuracy.accuracy, stats.correct as f64 / total as f64);
+        assert_eq!(
+            accuracy,
+            Accuracy {
+                correct: 5,
+                incorrect: 4,
+                accuracy: 5.0 / 9.0
+            }
+        );
+    }
+    #[test]
+    fn final_stats_case_errors() {
+        let input = ["the", "Cat", "sat", "ok"].map(String::from);
+        let correct = ["The", "cat", "sAT", "ok"].map(String::from);
+        let stats = FinalStats::calculate(&input, &correct, Duration::from_secs(12), &TestMode::Duration(Duration::from_secs(12)));
+        assert_eq!((stats.correct, stats.incorrect, stats.case_errors), (7, 0, 4));
+        // only the last word and its space count, words in the wrong case aren't correct
+        assert_eq!(stats.wpm, 3.0);
+        assert_eq!(
+            word_difference("Über", "über").collect_vec(),
+            [CharDiffKind::CaseError, CharDiffKind::Correct, CharDiffKind::Correct, CharDiffKind::Correct]
+        );
+        assert!(!words_match("Über", "über"));
+    }
+    #[test]
+    fn accuracy_formulas() {
+        use KeyStrokeKind::*;
+        // "abc" typed as "axb", fixed to "ab" and ended with a space before the "c"
+        let key_strokes = [Correct('a'), Incorrect('x'), Backspace(0), Correct('b'), Space(-1), Correct('d')]
+            .map(|ks| (Duration::ZERO, ks));
+        let input = ["ab", "d"].map(String::from);
+        let correct = ["abc", "de"].map(String::from);
+        let stats = FinalStats::calculate(&input, &correct, Duration::from_secs(12), &TestMode::Duration(Duration::from_secs(12)));
+        let accuracy = |formula| calculate_accuracy_with(formula, &key_strokes, &stats);
+        assert_eq!(accuracy(AccuracyFormula::Keystrokes), calculate_accuracy(&key_strokes));
+        assert_eq!(accuracy(AccuracyFormula::Keystrokes).accuracy, 3.0 / 5.0);
+        assert_eq!(accuracy(AccuracyFormula::Typed).accuracy, 3.0 / 4.0);
+        // the x was fixed, only the missed c counts against the text
+        assert_eq!(accuracy(AccuracyFormula::Text).accuracy, 3.0 / 4.0);
+        assert_eq!(accuracy(AccuracyFormula::Text).incorrect, 1);
+        assert_eq!("text".parse(), Ok(AccuracyFormula::Text));
+        assert_eq!(count_backspaces(&key_strokes), 1);
+    }
+    #[test]
+    fn case_mismatch() {
+        assert!(is_case_mismatch("a", "A"));
+        assert!(is_case_mismatch("É", "e\u{301}"));
+        assert!(!is_case_mismatch("a", "a"));
+        assert!(!is_case_mismatch("a", "b"));
+        assert!(!is_case_mismatch("1", "!"));
+        let key_strokes = [KeyStrokeKind::Correct('a'), KeyStrokeKind::WrongCase('B')]
+            .map(|ks| (Duration::ZERO, ks));
+        assert_eq!(calculate_accuracy(&key_strokes).accuracy, 1.0);
+    }
+    #[test]
+    fn rhythm() {
+        let key_strokes = |times: &[f64]| {
+            times
+                .iter()
+                .map(|&t| (Duration::from_secs_f64(t), KeyStrokeKind::Correct('a')))
+                .collect_vec()
+        };
+        // a key every 150ms, give or take a little
+        let steady: Vec<f64> = (0..40).map(|i| i as f64 * 0.15 + (i % 3) as f64 * 0.02).collect();
+        assert_eq!(typing_rhythm(&key_strokes(&steady)), Some(Rhythm::Steady));
+        // 8 keys 30ms apart, then a 2s pause
+        let bursty: Vec<f64> = (0..40).map(|i| (i / 8) as f64 * 2.0 + (i % 8) as f64 * 0.03).collect();
+        assert_eq!(typing_rhythm(&key_strokes(&bursty)), Some(Rhythm::Bursty));
+        assert_eq!(typing_rhythm(&key_strokes(&steady[..5])), None);
+        assert_eq!(typing_rhythm(&key_strokes(&[0.0; 20])), None);
+    }
+    #[test]
+    fn speed_units() {
+        // 300 characters in a minute
+        assert_eq!(normalize_speed(300.0, 60.0, SpeedUnit::Wpm), 60.0);
+        assert_eq!(normalize_speed(300.0, 60.0, SpeedUnit::Cpm), 300.0);
+        assert_eq!(normalize_speed(300.0, 30.0, SpeedUnit::Cpm), 600.0);
+        assert_eq!(normalize_speed(300.0, 0.0, SpeedUnit::Cpm), 0.0);
+        assert_eq!(normalize_wpm(300.0, 60.0), 60.0);
+        assert_eq!(SpeedUnit::Cpm.from_wpm(60.0), 300.0);
+        assert_eq!(SpeedUnit::Wpm.from_wpm(60.0), 60.0);
+
+        assert_eq!(SpeedUnit::Wpm.next(), SpeedUnit::Cpm);
+        assert_eq!(SpeedUnit::Cpm.next(), SpeedUnit::Wpm);
+        assert!("kpm".parse::<SpeedUnit>().is_err());
+        assert_eq!(SpeedUnit::Cpm.to_string(), "cpm");
+    }
+    #[test]
+    fn test_mode_from_str() {
+        assert_eq!("time:30".parse(), Ok(TestMode::Duration(Duration::from_secs(30))));
+        assert_eq!("words:50".parse(), Ok(TestMode::Words(50)));
+        assert!("words:".parse::<TestMode>().is_err());
+        assert!("30".parse::<TestMode>().is_err());
+        assert_eq!(TestMode::Words(50).to_string(), "words:50");
+        assert_eq!("chars:200".parse(), Ok(TestMode::Chars(200)));
+        assert_eq!(TestMode::Chars(200).to_string(), "chars:200");
+    }
+}
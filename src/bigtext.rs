@@ -0,0 +1,41 @@
+//! Digits drawn with blocks, five rows tall.
+
+const DIGITS: [[&str; 5]; 10] = [
+    ["███", "█ █", "█ █", "█ █", "███"],
+    ["  █", "  █", "  █", "  █", "  █"],
+    ["███", "  █", "███", "█  ", "███"],
+    ["███", "  █", "███", "  █", "███"],
+    ["█ █", "█ █", "███", "  █", "  █"],
+    ["███", "█  ", "███", "  █", "███"],
+    ["███", "█  ", "███", "█ █", "███"],
+    ["███", "  █", "  █", "  █", "  █"],
+    ["███", "█ █", "███", "█ █", "███"],
+    ["███", "█ █", "███", "  █", "███"],
+];
+
+pub const HEIGHT: usize = 5;
+
+/// The rows of `text` in big digits, anything but digits is left out.
+pub fn big_digits(text: &str) -> Vec<String> {
+    let glyphs: Vec<_> = text
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|digit| DIGITS[digit as usize])
+        .collect();
+    (0..HEIGHT)
+        .map(|row| glyphs.iter().map(|glyph| glyph[row]).collect::<Vec<_>>().join(" "))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn digits() {
+        assert_eq!(
+            big_digits("12"),
+            ["  █ ███", "  █   █", "  █ ███", "  █ █  ", "  █ ███"]
+        );
+        assert_eq!(big_digits("s"), ["", "", "", "", ""]);
+    }
+}
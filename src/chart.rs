@@ -0,0 +1,51 @@
+use anyhow::{anyhow, Result};
+use plotters::prelude::*;
+
+/// Draws the wpm chart of the results screen to an svg file at `path`, with
+/// the errors as red dots like on the screen.
+pub fn save_svg(path: &str, raw_wpms: &[(f64, f64)], errors_wpms: &[(f64, f64)], unit: &str) -> Result<()> {
+    let last_time = raw_wpms.iter().map(|&(time, _)| time).fold(0.1, f64::max);
+    let max_wpm = raw_wpms.iter().map(|&(_, wpm)| wpm).fold(1.0, f64::max) * 1.1;
+
+    let root = SVGBackend::new(path, (800, 400)).into_drawing_area();
+    // the errors of plotters borrow the backend, so they're turned into strings
+    let draw = |err: DrawingAreaErrorKind<_>| anyhow!("couldn't draw the chart: {err}");
+    root.fill(&WHITE).map_err(draw)?;
+    let mut chart = ChartBuilder::on(&root)
+        .margin(20)
+        .x_label_area_size(40)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0.0..last_time, 0.0..max_wpm)
+        .map_err(draw)?;
+    chart
+        .configure_mesh()
+        .x_desc("time (s)")
+        .y_desc(unit)
+        .draw()
+        .map_err(draw)?;
+    chart
+        .draw_series(LineSeries::new(raw_wpms.iter().copied(), &BLACK))
+        .map_err(draw)?;
+    chart
+        .draw_series(errors_wpms.iter().map(|&point| Circle::new(point, 3, RED.filled())))
+        .map_err(draw)?;
+    root.present().map_err(draw)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempPath;
+    #[test]
+    fn svg_chart() {
+        let path = TempPath::new("chart.svg");
+        let path = path.to_str().unwrap();
+        save_svg(path, &[(1.0, 40.0), (2.0, 60.0), (3.0, 50.0)], &[(2.0, 12.0)], "wpm").unwrap();
+        let svg = std::fs::read_to_string(path).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("<polyline"));
+        assert!(svg.contains("<circle"));
+        assert!(svg.contains("time (s)"));
+    }
+}
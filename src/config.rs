@@ -0,0 +1,170 @@
+//! Files crabtype keeps between runs.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::{analysis::TestMode, error::Result, words::Difficulty};
+
+/// The mode of a test without `--words`, `--duration` or a `default_mode` in the config.
+pub const DEFAULT_MODE: TestMode = TestMode::Duration(Duration::from_secs(30));
+
+/// `$XDG_CONFIG_HOME/crabtype`, falling back to `~/.config/crabtype`.
+pub fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .map(|dir| dir.join("crabtype"))
+}
+
+/// `$XDG_CACHE_HOME/crabtype`, falling back to `~/.cache/crabtype`.
+pub fn cache_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .map(|dir| dir.join("crabtype"))
+}
+
+/// The options of the most recent run, reused when crabtype is started without any.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct LastRun {
+    pub words: Option<usize>,
+    pub duration: Option<u64>,
+    pub words_file: Option<String>,
+    pub extra_words_file: Option<String>,
+    pub punctuate: bool,
+    pub no_shuffle: bool,
+    pub capitalize: bool,
+    pub difficulty: Option<Difficulty>,
+}
+
+impl LastRun {
+    /// Json like the rest of the files, not toml, so there's no other format to parse.
+    fn path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("last_run.json"))
+    }
+
+    pub fn load() -> Option<Self> {
+        Self::load_from(&Self::path()?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        match Self::path() {
+            Some(path) => self.save_to(&path),
+            None => Ok(()),
+        }
+    }
+
+    /// Forgets the last run, does nothing if there isn't one.
+    pub fn clear() -> Result<()> {
+        match Self::path() {
+            Some(path) if path.exists() => Ok(fs::remove_file(path)?),
+            _ => Ok(()),
+        }
+    }
+
+    fn load_from(path: &Path) -> Option<Self> {
+        serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Settings from `config.json`, which crabtype only reads.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Config {
+    /// the mode when none is given, written like `--mode`, e.g. `time:60` or `words:25`
+    #[serde(deserialize_with = "parse_mode")]
+    pub default_mode: Option<TestMode>,
+}
+
+impl Config {
+    fn path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("config.json"))
+    }
+
+    /// The defaults when there's no config file.
+    pub fn load() -> Result<Self> {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn load_from(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(serde_json::from_str(&text)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// `default_mode`, or [`DEFAULT_MODE`] when it isn't set.
+    pub fn default_mode(&self) -> TestMode {
+        self.default_mode.unwrap_or(DEFAULT_MODE)
+    }
+}
+
+fn parse_mode<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<TestMode>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|mode| mode.parse().map_err(de::Error::custom))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::TempPath, CrabtypeError};
+    #[test]
+    fn last_run_round_trip() {
+        let path = TempPath::new("last-run.json");
+        let last_run = LastRun {
+            words: Some(50),
+            duration: None,
+            words_file: Some("words.json".to_string()),
+            extra_words_file: Some("jargon.json".to_string()),
+            punctuate: true,
+            no_shuffle: false,
+            capitalize: false,
+            difficulty: Some(Difficulty::Hard),
+        };
+        last_run.save_to(&path).unwrap();
+        assert_eq!(LastRun::load_from(&path), Some(last_run));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(LastRun::load_from(&path), None);
+    }
+    #[test]
+    fn config_default_mode() {
+        let path = TempPath::new("config.json");
+        assert_eq!(Config::load_from(&path).unwrap().default_mode(), DEFAULT_MODE);
+        fs::write(&path, r#"{"default_mode": "words:25"}"#).unwrap();
+        assert_eq!(Config::load_from(&path).unwrap().default_mode(), TestMode::Words(25));
+        fs::write(&path, r#"{"default_mode": "minutes:2"}"#).unwrap();
+        assert!(matches!(Config::load_from(&path), Err(CrabtypeError::Json(_))));
+        fs::write(&path, "{}").unwrap();
+        assert_eq!(Config::load_from(&path).unwrap().default_mode(), DEFAULT_MODE);
+    }
+    #[test]
+    fn last_run_missing_fields() {
+        let last_run: LastRun = serde_json::from_str(r#"{"words": 25}"#).unwrap();
+        assert_eq!(
+            last_run,
+            LastRun {
+                words: Some(25),
+                ..Default::default()
+            }
+        );
+    }
+}
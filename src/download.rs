@@ -0,0 +1,258 @@
+//! Word lists downloaded with `--words-url`, kept in a cache keyed by the url.
+//!
+//! Only plain `http://` is supported, there's no tls without another dependency.
+
+use std::{
+    fs,
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    config::cache_dir,
+    error::{CrabtypeError, Result},
+    words::WordList,
+};
+
+/// A cached list younger than this is used without downloading it again.
+const CACHE_MAX_AGE: Duration = Duration::from_secs(60 * 60 * 24);
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Larger responses aren't word lists, the built in list is a few kilobytes.
+const MAX_RESPONSE: u64 = 16 * 1024 * 1024;
+
+/// Redirects followed before giving up.
+const MAX_REDIRECTS: usize = 5;
+
+/// The word list at `url`, from the cache when it was downloaded recently.
+/// When the download fails an older cached copy is used instead.
+pub fn word_list(url: &str) -> Result<WordList> {
+    word_list_cached(url, cache_dir().as_deref())
+}
+
+fn word_list_cached(url: &str, cache: Option<&Path>) -> Result<WordList> {
+    let cached = cache.map(|dir| dir.join(cache_file_name(url)));
+    let age = cached
+        .as_ref()
+        .and_then(|path| fs::metadata(path).ok())
+        .and_then(|metadata| metadata.modified().ok())
+        .map(|modified| SystemTime::now().duration_since(modified).unwrap_or_default());
+    if let (Some(path), Some(age)) = (&cached, age) {
+        // a cached copy that can't be read, like one cut short, is downloaded again
+        if let (true, Ok(word_list)) = (age < CACHE_MAX_AGE, load_cached(path)) {
+            return Ok(word_list);
+        }
+    }
+    match http_get(url) {
+        Ok(body) => {
+            let word_list = WordList::from_json(&String::from_utf8_lossy(&body), url)?;
+            if let Some(path) = &cached {
+                // the list was downloaded fine, it just has to be downloaded again next time
+                let _ = save(path, &body);
+            }
+            Ok(word_list)
+        }
+        Err(source) => cached
+            .filter(|_| age.is_some())
+            .and_then(|path| load_cached(&path).ok())
+            .ok_or_else(|| CrabtypeError::DownloadWordList {
+                url: url.to_string(),
+                source,
+            }),
+    }
+}
+
+fn load_cached(path: &Path) -> Result<WordList> {
+    let json = fs::read_to_string(path).map_err(|source| CrabtypeError::ReadWordsFile {
+        path: path.display().to_string(),
+        source,
+    })?;
+    WordList::from_json(&json, &path.display().to_string())
+}
+
+fn save(path: &Path, body: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, body)
+}
+
+/// A file name that only depends on the url, with a hash that stays the same between versions.
+fn cache_file_name(url: &str) -> String {
+    // 64 bit FNV-1a
+    let hash = url
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    format!("words-{hash:016x}.json")
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
+
+/// The body of a successful GET of an `http://` url, following redirects to other `http://` urls.
+fn http_get(url: &str) -> io::Result<Vec<u8>> {
+    let mut url = url.to_string();
+    for _ in 0..=MAX_REDIRECTS {
+        match get_once(&url)? {
+            Response::Body(body) => return Ok(body),
+            Response::Redirect(location) => {
+                url = match location.strip_prefix('/') {
+                    // relative to the host of the last url
+                    Some(path) => format!("http://{}/{path}", split_url(&url)?.0),
+                    None => location,
+                };
+            }
+        }
+    }
+    Err(io::Error::other(format!("more than {MAX_REDIRECTS} redirects")))
+}
+
+enum Response {
+    Body(Vec<u8>),
+    /// the `Location` of a redirect
+    Redirect(String),
+}
+
+/// The host with its port and the path of an `http://` url.
+fn split_url(url: &str) -> io::Result<(&str, &str)> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| invalid("only http:// urls can be downloaded, there's no tls"))?;
+    Ok(match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, "/"),
+    })
+}
+
+fn get_once(url: &str) -> io::Result<Response> {
+    let (host, path) = split_url(url)?;
+    let address = match host.contains(':') {
+        true => host.to_string(),
+        false => format!("{host}:80"),
+    };
+    let mut last_error = invalid(format!("`{host}` has no address"));
+    let mut stream = None;
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, TIMEOUT) {
+            Ok(connected) => {
+                stream = Some(connected);
+                break;
+            }
+            Err(err) => last_error = err,
+        }
+    }
+    let mut stream = stream.ok_or(last_error)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    // 1.0 so the body isn't chunked
+    let request = format!("GET {path} HTTP/1.0\r\nHost: {host}\r\nUser-Agent: crabtype\r\n\r\n");
+    stream.write_all(request.as_bytes())?;
+    let mut response = Vec::new();
+    // one byte more than allowed to tell a response that's too large
+    stream.take(MAX_RESPONSE + 1).read_to_end(&mut response)?;
+    if response.len() as u64 > MAX_RESPONSE {
+        return Err(io::Error::other(format!("the response is larger than {} MiB", MAX_RESPONSE >> 20)));
+    }
+
+    let header_end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(|| invalid("the response has no body"))?;
+    let head = String::from_utf8_lossy(&response[..header_end]);
+    let mut lines = head.lines();
+    let status = lines.next().unwrap_or_default();
+    match status.split_whitespace().nth(1) {
+        Some("200") => Ok(Response::Body(response.split_off(header_end + 4))),
+        Some("301" | "302" | "303" | "307" | "308") => lines
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("location"))
+            .map(|(_, location)| Response::Redirect(location.trim().to_string()))
+            .ok_or_else(|| io::Error::other(format!("`{status}` without a location"))),
+        _ => Err(io::Error::other(format!("the server answered `{status}`"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread};
+
+    use super::*;
+    use crate::test_util::TempPath;
+
+    /// Answers one request with `response` on a free port, returns the url to it.
+    fn serve_once(response: impl Into<String>) -> String {
+        let response = response.into();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/words.json", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                match stream.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        url
+    }
+
+    #[test]
+    fn download_and_cache() {
+        let cache = TempPath::new("cache");
+        let url = serve_once("HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n{\"name\": \"web\", \"words\": [\"a\", \"b\"]}");
+        let word_list = word_list_cached(&url, Some(&cache)).unwrap();
+        assert_eq!((word_list.name.as_str(), word_list.words.len()), ("web", 2));
+        // nothing listens anymore, the cached copy is used
+        assert_eq!(word_list_cached(&url, Some(&cache)).unwrap().words, ["a", "b"]);
+    }
+
+    #[test]
+    fn broken_cache_downloaded_again() {
+        let cache = TempPath::new("broken-cache");
+        let url = serve_once("HTTP/1.0 200 OK\r\n\r\n{\"name\": \"fresh\", \"words\": [\"a\"]}");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join(cache_file_name(&url)), "{\"name\": \"cut").unwrap();
+        assert_eq!(word_list_cached(&url, Some(&cache)).unwrap().name, "fresh");
+        assert_eq!(word_list_cached(&url, Some(&cache)).unwrap().name, "fresh");
+        // without a server or a usable copy it's the download that failed
+        fs::write(cache.join(cache_file_name(&url)), "{\"name\": \"cut").unwrap();
+        assert!(matches!(
+            word_list_cached(&url, Some(&cache)),
+            Err(CrabtypeError::DownloadWordList { .. })
+        ));
+    }
+
+    #[test]
+    fn follow_redirects() {
+        let target = serve_once("HTTP/1.0 200 OK\r\n\r\n{\"name\": \"moved\", \"words\": [\"a\"]}");
+        let url = serve_once(format!("HTTP/1.0 301 Moved Permanently\r\nlocation: {target}\r\n\r\n"));
+        assert_eq!(word_list_cached(&url, None).unwrap().name, "moved");
+    }
+
+    #[test]
+    fn download_errors() {
+        let url = serve_once("HTTP/1.0 404 Not Found\r\n\r\n");
+        let err = word_list_cached(&url, None).unwrap_err();
+        assert!(err.to_string().ends_with("the server answered `HTTP/1.0 404 Not Found`"), "{err}");
+        assert!(matches!(
+            word_list_cached("https://example.com/words.json", None),
+            Err(CrabtypeError::DownloadWordList { .. })
+        ));
+        let url = serve_once("HTTP/1.0 200 OK\r\n\r\nnot json");
+        assert!(matches!(word_list_cached(&url, None), Err(CrabtypeError::ParseWordList { .. })));
+    }
+
+    #[test]
+    fn cache_names() {
+        assert_eq!(cache_file_name("http://a"), cache_file_name("http://a"));
+        assert_ne!(cache_file_name("http://a"), cache_file_name("http://b"));
+        assert_eq!(cache_file_name(""), "words-cbf29ce484222325.json");
+    }
+}
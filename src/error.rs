@@ -0,0 +1,85 @@
+//! The error type of the library.
+
+use std::{error::Error, fmt, io};
+
+pub type Result<T, E = CrabtypeError> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+pub enum CrabtypeError {
+    /// reading or writing a file or the connection failed
+    Io(io::Error),
+    /// a config file, recording or message isn't the expected json
+    Json(serde_json::Error),
+    /// the words file at `path` couldn't be read
+    ReadWordsFile { path: String, source: io::Error },
+    /// the words file at `path` couldn't be written
+    WriteWordsFile { path: String, source: io::Error },
+    /// the word list at `url` couldn't be downloaded and isn't cached
+    DownloadWordList { url: String, source: io::Error },
+    /// the words file at `path` isn't a valid word list
+    ParseWordList { path: String, source: serde_json::Error },
+    /// there are no words in the named list or text
+    EmptyWordList(String),
+    /// there are no bundled snippets for the language
+    UnknownLanguage(String),
+    /// the other player closed the connection
+    Disconnected,
+}
+
+impl fmt::Display for CrabtypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // the wrapped errors are the source, so they aren't repeated here
+            Self::Io(_) => write!(f, "reading or writing failed"),
+            Self::Json(_) => write!(f, "invalid json"),
+            Self::ReadWordsFile { path, source } => write!(f, "could not read words file '{path}': {source}"),
+            Self::WriteWordsFile { path, source } => write!(f, "could not write words file '{path}': {source}"),
+            Self::DownloadWordList { url, source } => write!(f, "could not download words from '{url}': {source}"),
+            Self::ParseWordList { path, source } => write!(f, "could not parse words file '{path}': {source}"),
+            Self::EmptyWordList(name) => write!(f, "there are no words in {name}"),
+            Self::UnknownLanguage(language) => write!(
+                f,
+                "no code snippets for `{language}`, try one of {}",
+                crate::words::CODE_LANGUAGES.join(", ")
+            ),
+            Self::Disconnected => write!(f, "the other player disconnected"),
+        }
+    }
+}
+
+// the other messages already end with the error they wrap, so it isn't a source too
+impl Error for CrabtypeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CrabtypeError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for CrabtypeError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapped_errors_are_the_source() {
+        let err = CrabtypeError::from(io::Error::other("disk full"));
+        assert_eq!(err.to_string(), "reading or writing failed");
+        assert_eq!(err.source().unwrap().to_string(), "disk full");
+        let err = CrabtypeError::EmptyWordList("the text".to_string());
+        assert!(err.source().is_none());
+    }
+}
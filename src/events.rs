@@ -0,0 +1,34 @@
+//! Where `run_app` gets its terminal events from.
+
+use std::{io, time::Duration};
+
+use crossterm::event::{self, Event};
+
+pub trait EventSource {
+    /// Waits at most `timeout` for the next event, `None` if there wasn't one.
+    fn next_event(&mut self, timeout: Duration) -> io::Result<Option<Event>>;
+}
+
+/// The events of the real terminal.
+pub struct CrosstermEvents;
+
+impl EventSource for CrosstermEvents {
+    fn next_event(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        event::poll(timeout).and_then(|polled| polled.then(event::read).transpose())
+    }
+}
+
+/// A fixed list of events, given one after the other without waiting.
+/// Running out is an error so a script that doesn't quit can't hang.
+#[cfg(test)]
+pub struct ScriptedEvents<I>(pub I);
+
+#[cfg(test)]
+impl<I: Iterator<Item = Event>> EventSource for ScriptedEvents<I> {
+    fn next_event(&mut self, _timeout: Duration) -> io::Result<Option<Event>> {
+        match self.0.next() {
+            Some(event) => Ok(Some(event)),
+            None => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the script ran out of events")),
+        }
+    }
+}
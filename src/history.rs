@@ -0,0 +1,307 @@
+//! Results of finished tests, stored as json lines next to the config.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{analysis::TestMode, config::config_dir, error::Result};
+
+const SECS_PER_DAY: u64 = 60 * 60 * 24;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    /// seconds since the unix epoch
+    pub timestamp: u64,
+    pub mode: TestMode,
+    pub wpm: f64,
+    pub raw_wpm: f64,
+    pub accuracy: f64,
+    /// `language` of the word list, older entries don't have one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Days since the unix epoch, in the local timezone.
+    pub fn day(&self) -> u64 {
+        local_day(self.timestamp)
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+pub fn today() -> u64 {
+    local_day(now())
+}
+
+/// Days since the unix epoch in the local timezone, of seconds since the unix epoch.
+pub fn local_day(timestamp: u64) -> u64 {
+    timestamp.saturating_add_signed(utc_offset(timestamp)) / SECS_PER_DAY
+}
+
+/// Seconds the local timezone is ahead of UTC at `timestamp`, daylight saving time included.
+#[cfg(unix)]
+fn utc_offset(timestamp: u64) -> i64 {
+    let time = timestamp as libc::time_t;
+    // SAFETY: `tm` is plain data that localtime_r only writes to
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    match unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        true => 0,
+        false => tm.tm_gmtoff as i64,
+    }
+}
+
+/// Without a way to ask for the timezone, days are in UTC.
+#[cfg(not(unix))]
+fn utc_offset(_timestamp: u64) -> i64 {
+    0
+}
+
+fn path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("history.jsonl"))
+}
+
+pub fn append(entry: &HistoryEntry) -> Result<()> {
+    match path() {
+        Some(path) => append_to(&path, entry),
+        None => Ok(()),
+    }
+}
+
+/// Every stored entry, oldest first. Lines that can't be parsed are skipped.
+pub fn load() -> Vec<HistoryEntry> {
+    path().map(|path| load_from(&path)).unwrap_or_default()
+}
+
+fn append_to(path: &Path, entry: &HistoryEntry) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+fn load_from(path: &Path) -> Vec<HistoryEntry> {
+    fs::read_to_string(path)
+        .map(|contents| {
+            contents
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Amount of days in a row ending today with at least one test.
+/// A streak that ended yesterday still counts until today is over.
+pub fn streak(entries: &[HistoryEntry], today: u64) -> u32 {
+    let days: HashSet<u64> = entries.iter().map(HistoryEntry::day).collect();
+    let mut day = if days.contains(&today) {
+        today
+    } else {
+        today.saturating_sub(1)
+    };
+    let mut streak = 0;
+    while days.contains(&day) {
+        streak += 1;
+        if day == 0 {
+            break;
+        }
+        day -= 1;
+    }
+    streak
+}
+
+/// Amount of tests finished on `day`.
+pub fn sessions_on(entries: &[HistoryEntry], day: u64) -> usize {
+    entries.iter().filter(|e| e.day() == day).count()
+}
+
+/// The last run of `mode`, the entries are in the order they were saved.
+pub fn previous(entries: &[HistoryEntry], mode: TestMode) -> Option<&HistoryEntry> {
+    entries.iter().rev().find(|e| e.mode == mode)
+}
+
+/// The `n` best runs by wpm, optionally only the ones with the given mode.
+pub fn leaderboard(entries: &[HistoryEntry], mode: Option<TestMode>, n: usize) -> Vec<&HistoryEntry> {
+    let mut best: Vec<_> = entries
+        .iter()
+        .filter(|e| mode.is_none_or(|mode| e.mode == mode))
+        .collect();
+    best.sort_by(|l, r| r.wpm.total_cmp(&l.wpm));
+    best.truncate(n);
+    best
+}
+
+/// Totals over the whole history, see [`summary`].
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub tests: usize,
+    pub average_wpm: f64,
+    pub best_wpm: f64,
+    pub average_accuracy: f64,
+    /// amount of tests of each mode, e.g. `time:30`
+    pub modes: BTreeMap<String, usize>,
+    /// amount of tests in each language, `unknown` for lists without one
+    pub languages: BTreeMap<String, usize>,
+}
+
+/// Totals of the entries, `None` when there are none to average.
+pub fn summary(entries: &[HistoryEntry]) -> Option<Summary> {
+    if entries.is_empty() {
+        return None;
+    }
+    let count = entries.len() as f64;
+    let mut modes = BTreeMap::new();
+    let mut languages = BTreeMap::new();
+    for entry in entries {
+        *modes.entry(entry.mode.to_string()).or_default() += 1;
+        let language = entry.language.clone().unwrap_or_else(|| "unknown".to_string());
+        *languages.entry(language).or_default() += 1;
+    }
+    Some(Summary {
+        tests: entries.len(),
+        average_wpm: entries.iter().map(|e| e.wpm).sum::<f64>() / count,
+        best_wpm: entries.iter().map(|e| e.wpm).fold(0.0, f64::max),
+        average_accuracy: entries.iter().map(|e| e.accuracy).sum::<f64>() / count,
+        modes,
+        languages,
+    })
+}
+
+/// Formats days since the unix epoch as `YYYY-MM-DD`.
+pub fn format_day(day: u64) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = day as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::test_util::TempPath;
+
+    fn local_noon(day: u64) -> u64 {
+        let noon = day * SECS_PER_DAY + SECS_PER_DAY / 2;
+        noon.saturating_add_signed(-utc_offset(noon))
+    }
+
+    fn entry_on(day: u64) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: local_noon(day),
+            mode: TestMode::Duration(Duration::from_secs(30)),
+            wpm: 60.0,
+            raw_wpm: 65.0,
+            accuracy: 0.95,
+            language: Some("en".to_string()),
+        }
+    }
+
+    #[test]
+    fn streak_counts_days() {
+        let entries = [10, 11, 11, 12, 14, 15].map(entry_on);
+        assert_eq!(streak(&entries, 15), 2);
+        assert_eq!(streak(&entries, 16), 2);
+        assert_eq!(streak(&entries, 17), 0);
+        assert_eq!(streak(&entries, 12), 3);
+        assert_eq!(sessions_on(&entries, 11), 2);
+    }
+
+    #[test]
+    fn append_and_load() {
+        let path = TempPath::new("history.jsonl");
+        append_to(&path, &entry_on(1)).unwrap();
+        append_to(&path, &entry_on(2)).unwrap();
+        assert_eq!(load_from(&path), [entry_on(1), entry_on(2)]);
+    }
+
+    #[test]
+    fn leaderboard_sorted_and_filtered() {
+        let mut entries = [1, 2, 3].map(entry_on).to_vec();
+        entries[0].wpm = 50.0;
+        entries[1].wpm = 80.0;
+        entries[2].mode = TestMode::Words(25);
+        let best = leaderboard(&entries, None, 2);
+        assert_eq!(best, [&entries[1], &entries[2]]);
+        let best = leaderboard(&entries, Some(TestMode::Words(25)), 10);
+        assert_eq!(best, [&entries[2]]);
+        assert!(leaderboard(&[], None, 10).is_empty());
+    }
+
+    #[test]
+    fn previous_of_the_same_mode() {
+        let mut entries = [1, 2, 3].map(entry_on).to_vec();
+        entries[2].mode = TestMode::Words(25);
+        assert_eq!(previous(&entries, TestMode::Duration(Duration::from_secs(30))), Some(&entries[1]));
+        assert_eq!(previous(&entries, TestMode::Words(25)), Some(&entries[2]));
+        assert_eq!(previous(&entries, TestMode::Words(10)), None);
+    }
+
+    #[test]
+    fn summary_totals() {
+        assert_eq!(summary(&[]), None);
+        let mut entries = [1, 2, 3].map(entry_on).to_vec();
+        entries[0].wpm = 40.0;
+        entries[1].wpm = 80.0;
+        entries[1].accuracy = 0.8;
+        entries[2].mode = TestMode::Words(25);
+        entries[2].language = None;
+        let summary = summary(&entries).unwrap();
+        assert_eq!(summary.tests, 3);
+        assert_eq!(summary.average_wpm, 60.0);
+        assert_eq!(summary.best_wpm, 80.0);
+        assert!((summary.average_accuracy - 0.9).abs() < 1e-9);
+        assert_eq!(
+            summary.modes.into_iter().collect::<Vec<_>>(),
+            [("time:30".to_string(), 2), ("words:25".to_string(), 1)]
+        );
+        assert_eq!(
+            summary.languages.into_iter().collect::<Vec<_>>(),
+            [("en".to_string(), 2), ("unknown".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn entry_without_language() {
+        let entry: HistoryEntry = serde_json::from_str(
+            r#"{"timestamp": 0, "mode": {"Words": 10}, "wpm": 50.0, "raw_wpm": 55.0, "accuracy": 0.9}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.language, None);
+    }
+
+    #[test]
+    fn local_days() {
+        assert_eq!(local_day(local_noon(19_723)), 19_723);
+        // no timezone is more than a day away from UTC
+        assert!(today().abs_diff(now() / SECS_PER_DAY) <= 1);
+    }
+
+    #[test]
+    fn days_to_dates() {
+        assert_eq!(format_day(0), "1970-01-01");
+        assert_eq!(format_day(19_723), "2024-01-01");
+        assert_eq!(format_day(19_782), "2024-02-29");
+    }
+}
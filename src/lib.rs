@@ -0,0 +1,18 @@
+//! The logic behind crabtype, a typing test in the terminal.
+//!
+//! The terminal interface itself lives in the binary.
+
+pub mod analysis;
+pub mod config;
+#[cfg(feature = "words-url")]
+pub mod download;
+pub mod error;
+pub mod history;
+pub mod net;
+pub mod replay;
+pub mod words;
+
+#[cfg(test)]
+mod test_util;
+
+pub use error::CrabtypeError;
@@ -0,0 +1,54 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    sync::Mutex,
+    time::Instant,
+};
+
+use anyhow::{Context, Result};
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Writes log records to a file, the terminal is taken by the ui.
+struct FileLogger {
+    file: Mutex<BufWriter<File>>,
+    start: Instant,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let Ok(mut file) = self.file.lock() else {
+            return;
+        };
+        // a line that can't be written isn't worth stopping the test for
+        let _ = writeln!(
+            file,
+            "{:>9.3} {:<5} {}: {}",
+            self.start.elapsed().as_secs_f64(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        let _ = file.flush();
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Logs everything to `path` from now on, overwriting what was there.
+pub fn init(path: &str) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("couldn't create the log file {path}"))?;
+    log::set_boxed_logger(Box::new(FileLogger {
+        file: Mutex::new(BufWriter::new(file)),
+        start: Instant::now(),
+    }))?;
+    log::set_max_level(LevelFilter::Trace);
+    Ok(())
+}
@@ -1,39 +1,247 @@
-use std::{borrow::Cow, fs, io, path::Path, time::Duration};
+use std::{
+    fs,
+    io::{self, Read},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+    time::{Duration, Instant},
+};
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        self, DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture, Event,
+        KeyCode, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
     },
+    cursor::Show,
     execute,
-    style::ContentStyle,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+};
+use crabtype::{
+    analysis::{calculate_accuracy, count_backspaces, typing_rhythm, AccuracyFormula, FinalStats, KeyStrokeKind, SpeedUnit, TestMode},
+    config::{Config, LastRun},
+    history,
+    net::{Connection, Message, Opponent},
+    replay::Recording,
+    words::{
+        code_snippet, daily_seed, generate_words, Difficulty, WordList, WordOptions, WordPool, CODE_LANGUAGES,
+    },
 };
-use rand::{distributions::uniform::SampleRange, seq::SliceRandom};
 use ratatui::prelude::*;
-use serde::Deserialize;
-use strum::IntoEnumIterator;
+
+/// Logs to the `--log-file` with the `logging` feature, e.g. `log_event!(info, "test started")`.
+/// Without the feature the arguments are still checked but nothing is logged.
+macro_rules! log_event {
+    ($level:ident, $($arg:tt)*) => {{
+        #[cfg(feature = "logging")]
+        log::$level!($($arg)*);
+        #[cfg(not(feature = "logging"))]
+        let _ = format_args!($($arg)*);
+    }};
+}
+
 mod typingwidget;
+mod bigtext;
+mod events;
+use events::{CrosstermEvents, EventSource};
 
 mod states;
 use states::*;
+#[cfg(feature = "sound")]
+mod sound;
+#[cfg(feature = "svg")]
+mod chart;
+#[cfg(feature = "logging")]
+mod logging;
+#[cfg(test)]
+mod test_util;
 
-use clap::Args;
+use clap::{
+    builder::{PossibleValuesParser, RangedU64ValueParser},
+    Args,
+};
 use clap::Parser;
 
-use rand::prelude::*;
+use rand::{prelude::*, rngs::SmallRng};
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, long_version = long_version(), about, long_about = None)]
 struct Cli {
     #[command(flatten)]
     mode: Mode,
     #[arg(long)]
     words_file: Option<String>,
+    /// Download the word list from this http url, it's kept for a day so it isn't downloaded every time
+    #[cfg(feature = "words-url")]
+    #[arg(long, conflicts_with_all = ["words_file", "text", "code", "daily"])]
+    words_url: Option<String>,
+    /// Add the words of this file to the word list, without the ones it already has
+    #[arg(long)]
+    extra_words_file: Option<String>,
+    /// Type the text in this file in order, keeping its lines, `-` reads stdin
+    #[arg(long, conflicts_with_all = ["words_file", "extra_words_file"])]
+    text: Option<String>,
+    /// Type a snippet of code in this language
+    #[arg(long, conflicts_with_all = ["words_file", "extra_words_file", "text"], value_parser = PossibleValuesParser::new(CODE_LANGUAGES))]
+    code: Option<String>,
+    /// Type the text on the clipboard in order
+    #[cfg(feature = "clipboard")]
+    #[arg(long, conflicts_with_all = ["words_file", "extra_words_file", "text", "code", "daily"])]
+    words_from_clipboard: bool,
     #[arg(short, long)]
     punctuate: bool,
+    /// Only type the `easy`, `medium` or `hard` words of lists ordered by how common the words are
+    #[arg(long, value_enum)]
+    difficulty: Option<Difficulty>,
+    /// Capitalize random words, without punctuation
+    #[arg(long)]
+    capitalize: bool,
+    /// Highlight the punctuation in the text that isn't typed yet
+    #[arg(long)]
+    punctuation_hints: bool,
+    /// Don't draw the word being typed in bold, only its letters are colored
+    #[arg(long)]
+    no_active_word: bool,
+    /// Don't dim the lines above the one being typed
+    #[arg(long)]
+    no_dim_completed: bool,
+    /// Only show the word being typed and this many words after it, 1 by default
+    #[arg(long, value_name = "WORDS", num_args = 0..=1, default_missing_value = "1")]
+    reveal: Option<usize>,
+    /// Take the challenge of the day, the same words and mode for everyone on the same day
+    #[arg(long, conflicts_with_all = [
+        "seed", "words", "duration", "max_chars", "words_file", "extra_words_file", "text", "code", "punctuate",
+        "capitalize", "difficulty", "no_shuffle", "loop_after", "host", "join", "watch", "edit",
+    ])]
+    daily: bool,
+    /// Keep the words in the order of the word list
+    #[arg(long)]
+    no_shuffle: bool,
     #[arg(long, short)]
     seed: Option<u64>,
+    /// Don't save these options for the next run without arguments
+    #[arg(long)]
+    no_remember: bool,
+    /// Forget the options saved from the last run
+    #[arg(long)]
+    reset: bool,
+    /// Keep the progress bar yellow instead of coloring it by accuracy
+    #[arg(long)]
+    plain_gauge: bool,
+    /// Show the time or words left in big digits below the text
+    #[arg(long)]
+    big_timer: bool,
+    /// Show the wpm of the last seconds next to the progress bar
+    #[arg(long)]
+    sparkline: bool,
+    /// Flash a beat at this many beats per minute next to the progress bar, to practice a steady rhythm
+    #[arg(long, value_name = "BPM", value_parser = RangedU64ValueParser::<u16>::new().range(1..=600))]
+    metronome: Option<u16>,
+    /// Show the accuracy of the last keys next to the progress bar, green at or above this percentage and red below it
+    #[arg(long, value_parser = RangedU64ValueParser::<u8>::new().range(0..=100))]
+    accuracy_goal: Option<u8>,
+    /// Hide the time and progress until the results
+    #[arg(long, conflicts_with = "big_timer")]
+    hide_progress: bool,
+    /// Quit after this many seconds without key presses, unless a test is running
+    #[arg(long)]
+    idle_timeout: Option<u64>,
+    /// Where to place the text vertically
+    #[arg(long, value_enum, default_value_t)]
+    position: TextPosition,
+    /// Don't count letters typed in the wrong case against the accuracy
+    #[arg(long)]
+    lenient_case: bool,
+    /// Keep the finished text on screen for this many milliseconds before the results, any key skips it
+    #[arg(long, default_value_t = 0)]
+    finish_hold: u64,
+    /// Show this character between the words, which are still typed with a space
+    #[arg(long)]
+    separator: Option<char>,
+    /// Columns between the words
+    #[arg(long, default_value_t = 1, value_parser = RangedU64ValueParser::<u16>::new().range(1..=8))]
+    separator_width: u16,
+    /// Start a new test this many seconds after the results are shown, until a key is pressed
+    #[arg(long = "loop", value_name = "SECONDS", num_args = 0..=1, default_missing_value = "5", conflicts_with_all = ["host", "join", "watch", "edit"])]
+    loop_after: Option<u64>,
+    /// Let backspace go back into words that are already correct
+    #[arg(long)]
+    free_backspace: bool,
+    /// Don't flash the space before the word when backspace can't go back into a correct word
+    #[arg(long)]
+    no_boundary_flash: bool,
+    /// Lay the text out from right to left, on by default for lists in a right to left language
+    #[arg(long)]
+    rtl: bool,
+    /// Play a click on every key press
+    #[cfg(feature = "sound")]
+    #[arg(long)]
+    sound: bool,
+    /// Host a race on this address, e.g. `0.0.0.0:7878`, and wait for another player
+    #[arg(long, conflicts_with = "join")]
+    host: Option<String>,
+    /// Join a race hosted on this address, the host decides the words and mode
+    #[arg(long)]
+    join: Option<String>,
+    /// Save the run to this file when it's finished, it can be watched with --watch
+    #[arg(long)]
+    export: Option<String>,
+    /// Watch a run saved with --export
+    #[arg(long, conflicts_with_all = ["host", "join"])]
+    watch: Option<String>,
+    /// Show speeds in words or characters per minute, `u` switches on the results
+    #[arg(long, value_enum, default_value_t)]
+    unit: SpeedUnit,
+    /// Save the wpm chart of the results to this svg file
+    #[cfg(feature = "svg")]
+    #[arg(long)]
+    chart_out: Option<String>,
+    /// Average the wpm chart over this many points
+    #[arg(long, default_value_t = 0)]
+    chart_smoothing: usize,
+    /// Amount of points in the wpm chart, at most one every half a second
+    #[arg(long, default_value_t = CHART_POINTS, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    chart_points: usize,
+    /// Calculate the accuracy from every `keystrokes`, only the `typed` keys without
+    /// skipped letters, or the final `text` without the mistakes that were fixed
+    #[arg(long, value_enum, default_value_t)]
+    accuracy: AccuracyFormula,
+    /// Fail runs with a lower accuracy than this percentage, they aren't saved to the history
+    #[arg(long, value_parser = RangedU64ValueParser::<u8>::new().range(0..=100))]
+    require_accuracy: Option<u8>,
+    /// Print the words of the test and the seed instead of starting it
+    #[arg(long, conflicts_with_all = ["host", "join", "watch"])]
+    dry_run: bool,
+    /// Draw below the prompt instead of on a screen of its own, so the test stays in the scrollback
+    #[arg(long, conflicts_with_all = ["edit", "watch"])]
+    inline: bool,
+    /// Don't print the seed or anything else when the test is over
+    #[arg(long, short)]
+    quiet: bool,
+    /// Add and remove the words of a word list file, it's saved when quitting
+    #[arg(long, conflicts_with_all = ["host", "join", "watch", "dry_run"])]
+    edit: Option<String>,
+    /// Print the results of a run saved with --export as json, without the terminal ui
+    #[arg(long, hide = true)]
+    headless: Option<String>,
+    /// Print the 10 best runs from the history and exit
+    #[arg(long)]
+    leaderboard: bool,
+    /// Only show runs with this mode on the leaderboard, e.g. `time:30` or `words:50`
+    #[arg(long = "mode", requires = "leaderboard")]
+    leaderboard_mode: Option<TestMode>,
+    /// Log what happens during the run to this file, to find out what went wrong
+    #[cfg(feature = "logging")]
+    #[arg(long)]
+    log_file: Option<String>,
+    /// Print totals over all runs in the history and exit
+    #[arg(long, conflicts_with = "leaderboard")]
+    stats_summary: bool,
 }
 
 #[derive(Args, Debug)]
@@ -43,168 +251,553 @@ struct Mode {
     words: Option<usize>,
     #[arg(long, short)]
     duration: Option<u64>,
-}
-#[derive(Deserialize, Debug)]
-#[allow(unused)]
-struct WordList {
-    name: String,
-    words: Vec<String>,
+    /// End the test after typing this many characters, spaces included
+    #[arg(long)]
+    max_chars: Option<usize>,
 }
 pub struct App {
     word_list: WordList,
+    /// the options the words were generated with, after filling in the last run
+    options: LastRun,
+    /// a text is typed as is, its words aren't generated from the list
+    in_order: bool,
+    /// seeds the word order and punctuation, printed so a run can be repeated
+    seed: u64,
+    /// the day of the challenge with `--daily`
+    daily: Option<u64>,
     state: Option<Box<dyn State>>,
+    opponent: Option<Opponent>,
+    /// where to save the run when it's finished
+    export: Option<String>,
+    chart_smoothing: usize,
+    chart_points: usize,
+    unit: SpeedUnit,
+    idle_timeout: Option<Duration>,
+    /// how long the results are shown before the next test starts with `--loop`
+    loop_after: Option<Duration>,
+    accuracy_formula: AccuracyFormula,
+    /// lowest accuracy in percent for a run to pass
+    required_accuracy: Option<u8>,
+    #[cfg(feature = "sound")]
+    sound: Option<sound::Sound>,
+    /// where to save the chart of the results
+    #[cfg(feature = "svg")]
+    chart_out: Option<String>,
 }
 
-use strum::EnumIter;
-#[derive(EnumIter, Clone, Copy, PartialEq)]
-enum PunctuationKind {
-    Period,
-    Comma,
-    Hyphen,
-    Parantheses,
-    Exclamation,
-    Semicolon,
-    Colon,
-    DQuotes,
-    Quotes,
-}
-impl Into<char> for PunctuationKind {
-    fn into(self) -> char {
-        use PunctuationKind::*;
-        match self {
-            Period => '.',
-            Comma => ',',
-            Hyphen => '-',
-            Parantheses => ')',
-            Exclamation => '!',
-            Semicolon => ';',
-            Colon => ':',
-            DQuotes => '"',
-            Quotes => '\'',
+impl App {
+    /// The words of another test with the same options.
+    pub fn test_words(&self, seed: u64) -> Vec<String> {
+        test_words(&self.word_list, &self.options, self.in_order, seed)
+    }
+
+    /// Whether the words of a test are shuffled, and so the pool of a duration test.
+    pub fn shuffle(&self) -> bool {
+        !self.options.no_shuffle && !self.in_order
+    }
+
+    /// Plays the sound for a key stroke when sounds are enabled.
+    pub fn key_sound(&self, _key_stroke: &KeyStrokeKind) {
+        #[cfg(feature = "sound")]
+        if let Some(sound) = &self.sound {
+            sound.play(_key_stroke)
         }
     }
 }
 
-fn punctuate<R: Rng, S: SampleRange<usize> + Clone>(
-    words: Vec<String>,
-    jump_range: S,
-    rand: &mut R,
-) -> Vec<String> {
-    use PunctuationKind as PK;
-    let mut capitalize_next = true;
-    let mut new_words = Vec::new();
+fn main() -> Result<()> {
+    let args: Cli = Cli::parse();
+    #[cfg(feature = "logging")]
+    if let Some(path) = &args.log_file {
+        logging::init(path)?;
+    }
+    if args.leaderboard {
+        print_leaderboard(args.leaderboard_mode);
+        return Ok(());
+    }
+    if args.stats_summary {
+        print_stats_summary();
+        return Ok(());
+    }
+    if let Some(path) = &args.headless {
+        return print_headless(path);
+    }
+    if args.reset {
+        LastRun::clear()?;
+    }
+    let day = history::today();
+    let seed = match args.daily {
+        true => daily_seed(day),
+        false => args.seed.unwrap_or(thread_rng().gen()),
+    };
+    let mut rng = SmallRng::seed_from_u64(seed);
+
+    // a text is typed in order as is, so it isn't shuffled or punctuated
+    let text = match (&args.text, &args.code) {
+        (Some(path), _) => Some(read_text(path)?),
+        (_, Some(language)) => Some(code_snippet(language, &mut rng)?),
+        _ => None,
+    };
+    #[cfg(feature = "clipboard")]
+    let text = match args.words_from_clipboard {
+        true => Some(read_clipboard()?),
+        false => text,
+    };
+    let in_order = text.is_some();
+
+    let options = LastRun {
+        words: args.mode.words,
+        duration: args.mode.duration,
+        words_file: args.words_file,
+        extra_words_file: args.extra_words_file,
+        punctuate: args.punctuate,
+        no_shuffle: args.no_shuffle,
+        capitalize: args.capitalize,
+        difficulty: args.difficulty,
+    };
+    // without any options, repeat the last run
+    let options = if args.daily {
+        // whatever was picked last time, everyone takes the same test
+        daily_options()
+    } else if options == LastRun::default() && !args.reset && !in_order {
+        LastRun::load().unwrap_or_default()
+    } else {
+        // the host's options decide a race, they aren't the joiner's own
+        if !args.no_remember && !args.dry_run && !in_order && args.join.is_none() {
+            // not being able to remember the options shouldn't stop the test
+            let _ = options.save();
+        }
+        options
+    };
+
+    let recording = args.watch.as_deref().map(Recording::load).transpose()?;
+    let mut editor = args.edit.as_deref().map(EditorState::open).transpose()?;
+    if let Some(editor) = &mut editor {
+        editor.quiet = args.quiet;
+    }
+
+    let connection = match (&args.host, &args.join) {
+        (Some(addr), _) => {
+            if !args.quiet {
+                println!("waiting for a player to join on {addr}");
+            }
+            Some(Connection::host(addr)?)
+        }
+        (_, Some(addr)) => Some(Connection::join(addr)?),
+        _ => None,
+    };
 
-    let punctuations: Vec<PK> = PK::iter().collect();
-    let weights = [3, 2, 2, 2, 2, 1, 2, 2, 2];
-    debug_assert_eq!(punctuations.len(), weights.len());
+    let mut word_list = match text {
+        Some(text) => text,
+        #[cfg(feature = "words-url")]
+        None if args.words_url.is_some() => crabtype::download::word_list(args.words_url.as_deref().unwrap())?,
+        None => WordList::load(options.words_file.as_deref())?,
+    };
+    if let (Some(path), false) = (&options.extra_words_file, in_order) {
+        word_list.merge(WordList::load(Some(path))?);
+    }
 
-    let dist = rand::distributions::WeightedIndex::new(weights).unwrap();
+    let changed = word_list.sanitize();
+    if !changed.is_empty() {
+        eprintln!("warning: removed characters that can't be typed from the words {changed:?}");
+    }
+    if word_list.words.is_empty() {
+        bail!("there are no words left in '{}'", word_list.name);
+    }
+    let shuffle = !options.no_shuffle && !in_order;
+    let mut words = test_words(&word_list, &options, in_order, seed);
 
-    let mut next_index = rand.gen_range(jump_range.clone());
+    // a character limit isn't remembered, it's only for the text at hand
+    let mode = match (args.mode.max_chars, options.words, options.duration) {
+        (Some(chars), _, _) => TestMode::Chars(chars),
+        (_, Some(words), _) => TestMode::Words(words),
+        (_, _, Some(duration)) => TestMode::Duration(Duration::from_secs(duration)),
+        // the whole text by default
+        _ if in_order => TestMode::Words(words.len()),
+        _ => Config::load().context("couldn't read config.json")?.default_mode(),
+    };
 
-    for (i, mut word) in words.into_iter().enumerate() {
-        if capitalize_next {
-            capitalize_next = false;
-            word[0..1].make_ascii_uppercase();
+    if args.dry_run {
+        let count = match mode {
+            TestMode::Words(count) => count,
+            TestMode::Duration(_) | TestMode::Chars(_) => words.len(),
+        };
+        println!("{}", words[..count.min(words.len())].join(" "));
+        println!("seed:");
+        println!("{}", seed);
+        return Ok(());
+    }
+
+    // the host decides the words, so both players type the same ones
+    let pool_seed = rng.gen();
+    let (seed, pool_seed, mode) = match &connection {
+        Some(connection) if args.join.is_some() => match connection.recv()? {
+            Message::Start {
+                seed,
+                pool_seed,
+                mode,
+                words: host_words,
+                line_breaks,
+            } => {
+                words = host_words;
+                word_list.line_breaks = line_breaks;
+                (seed, pool_seed, mode)
+            }
+            message => bail!("expected the host to start the race, got {message:?}"),
+        },
+        Some(connection) => {
+            connection.send(&Message::Start {
+                seed,
+                pool_seed,
+                mode,
+                words: words.clone(),
+                line_breaks: word_list.line_breaks.clone(),
+            })?;
+            (seed, pool_seed, mode)
         }
-        if i == next_index {
-            next_index += rand.gen_range(jump_range.clone());
-            let pk = punctuations[dist.sample(rand)];
-            let c: char = pk.into();
-            match pk {
-                PK::Exclamation | PK::Period => {
-                    capitalize_next = true;
-                    word.push(c)
+        None => (seed, pool_seed, mode),
+    };
+
+    log_event!(
+        info,
+        "{} words from {} with seed {seed}, mode {mode}, {options:?}",
+        words.len(),
+        word_list.name
+    );
+    let mut typing_state = TypingState::new(words.clone(), mode);
+    if let TestMode::Duration(_) = mode {
+        let pool_rng = SmallRng::seed_from_u64(pool_seed);
+        typing_state.refill_from(WordPool::new(words, pool_rng, shuffle));
+    }
+    typing_state.line_breaks = word_list.line_breaks.clone();
+    typing_state.accuracy_gauge = !args.plain_gauge;
+    typing_state.big_timer = args.big_timer;
+    typing_state.hide_progress = args.hide_progress;
+    typing_state.sparkline = args.sparkline;
+    typing_state.accuracy_goal = args.accuracy_goal;
+    typing_state.metronome = args.metronome.map(|bpm| Duration::from_secs_f64(60.0 / bpm as f64));
+    typing_state.text_position = args.position;
+    typing_state.lenient_case = args.lenient_case;
+    typing_state.free_backspace = args.free_backspace;
+    typing_state.boundary_flash = !args.no_boundary_flash;
+    typing_state.rtl = args.rtl || word_list.is_rtl();
+    typing_state.separator = Separator {
+        width: args.separator_width,
+        glyph: args.separator,
+    };
+    typing_state.finish_hold = Duration::from_millis(args.finish_hold);
+    typing_state.punctuation_hints = args.punctuation_hints;
+    typing_state.active_word = !args.no_active_word;
+    typing_state.dim_completed_lines = !args.no_dim_completed;
+    typing_state.reveal = args.reveal;
+    let editing = editor.is_some();
+    let state: Box<dyn State> = match (editor, recording) {
+        (Some(editor), _) => Box::new(editor),
+        (_, Some(recording)) => Box::new(ReplayState::new(recording)),
+        _ => Box::new(typing_state),
+    };
+    let mut app = App {
+        state: Some(state),
+        word_list,
+        options,
+        in_order,
+        seed,
+        daily: args.daily.then_some(day),
+        opponent: connection.map(Opponent::new),
+        export: args.export,
+        chart_smoothing: args.chart_smoothing,
+        chart_points: args.chart_points,
+        unit: args.unit,
+        accuracy_formula: args.accuracy,
+        required_accuracy: args.require_accuracy,
+        idle_timeout: args.idle_timeout.map(Duration::from_secs),
+        loop_after: args.loop_after.map(Duration::from_secs),
+        #[cfg(feature = "sound")]
+        sound: args.sound.then(sound::Sound::new).flatten(),
+        #[cfg(feature = "svg")]
+        chart_out: args.chart_out,
+    };
+
+    // in raw mode Ctrl+C is a key press, a signal comes from outside and still restores the terminal
+    ctrlc::set_handler(|| QUIT.store(true, Ordering::Relaxed))?;
+    // everything that can fail is done before taking over the terminal, so errors are readable
+    let mut terminal = setup_terminal(args.inline)?;
+    let res = run_app(&mut terminal, &mut app, &mut CrosstermEvents);
+    if args.inline {
+        // the prompt comes back below the last frame instead of over it
+        let bottom = terminal.get_frame().size().bottom();
+        terminal.set_cursor(0, bottom.saturating_sub(1))?;
+    }
+    restore_terminal(args.inline)?;
+    if args.inline {
+        println!();
+    }
+
+    match res {
+        Ok(state) => {
+            if let Some(results) = state.results_line(&app).filter(|_| !args.quiet) {
+                match app.daily {
+                    // to share with the others who took the same challenge
+                    Some(day) => println!("crabtype daily {}: {results}", history::format_day(day)),
+                    None if args.inline => println!("{results}"),
+                    None => {}
                 }
-                PK::DQuotes | PK::Quotes => word = format!("{c}{word}{c}"),
-                PK::Parantheses => word = format!("({word})"),
-                PK::Hyphen => new_words.push(c.into()),
-                _ => word.push(c),
             }
+            state.quit()?
         }
-        new_words.push(word);
+        Err(err) => eprintln!("{err:?}"),
     }
-    new_words
+    if !editing && !args.quiet {
+        println!("seed:");
+        println!("{}", seed);
+    }
+    Ok(())
 }
 
-fn main() -> Result<()> {
-    let args: Cli = Cli::parse();
-    // setup terminal
+/// The version with the enabled cargo features, for `--version`.
+fn long_version() -> &'static str {
+    static LONG_VERSION: OnceLock<String> = OnceLock::new();
+    LONG_VERSION.get_or_init(|| {
+        let features = [
+            ("sound", cfg!(feature = "sound")),
+            ("svg", cfg!(feature = "svg")),
+            ("clipboard", cfg!(feature = "clipboard")),
+            ("logging", cfg!(feature = "logging")),
+            ("words-url", cfg!(feature = "words-url")),
+        ]
+        .into_iter()
+        .filter_map(|(feature, enabled)| enabled.then_some(feature))
+        .collect::<Vec<_>>();
+        format!(
+            "{}\nfeatures: {}\nwords: {}",
+            env!("CARGO_PKG_VERSION"),
+            if features.is_empty() { "none".to_string() } else { features.join(", ") },
+            WordList::built_in().name,
+        )
+    })
+}
+
+/// Rows drawn below the prompt with `--inline`.
+const INLINE_HEIGHT: u16 = 16;
+
+/// Takes over the whole screen, or only `INLINE_HEIGHT` rows below the prompt when `inline`.
+fn setup_terminal(inline: bool) -> Result<Terminal<states::Backend>> {
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-    
-
-    let contents: Cow<'_, str> = match args.words_file {
-        Some(path) => {
-            let c = fs::read_to_string(Path::new(&path))?;
-            c.into()
-        }
-        None => include_str!("../words/english_1k.json").into(),
+    let backend = CrosstermBackend::new(Box::new(io::stdout()) as Box<dyn io::Write>);
+    let setup = match inline {
+        // the mouse is left to the terminal, so its scrollback can still be scrolled
+        true => execute!(io::stdout(), EnableFocusChange).and_then(|_| {
+            Terminal::with_options(backend, TerminalOptions { viewport: Viewport::Inline(INLINE_HEIGHT) })
+        }),
+        false => execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture, EnableFocusChange)
+            .and_then(|_| Terminal::new(backend)),
     };
+    // without it most terminals send Ctrl+Enter as a plain Enter
+    let setup = setup.and_then(|terminal| {
+        if supports_keyboard_enhancement().unwrap_or(false) {
+            execute!(
+                io::stdout(),
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+            )?;
+            KEYBOARD_ENHANCED.store(true, Ordering::Relaxed);
+        }
+        Ok(terminal)
+    });
+    if setup.is_err() {
+        let _ = restore_terminal(inline);
+    }
+    Ok(setup?)
+}
 
-    let mut word_list = serde_json::from_str::<WordList>(&contents)?;
+/// Whether `setup_terminal` pushed keyboard enhancement flags that have to be popped again.
+static KEYBOARD_ENHANCED: AtomicBool = AtomicBool::new(false);
 
-    let seed = args.seed.unwrap_or(thread_rng().gen());
-    let mut rng = rand::rngs::SmallRng::seed_from_u64(seed);
-    word_list.words.shuffle(&mut rng);
-    if args.punctuate {
-        word_list.words = punctuate(word_list.words, 2..=4, &mut rng);
+fn restore_terminal(inline: bool) -> Result<()> {
+    if KEYBOARD_ENHANCED.swap(false, Ordering::Relaxed) {
+        execute!(io::stdout(), PopKeyboardEnhancementFlags)?;
+    }
+    disable_raw_mode()?;
+    if !inline {
+        execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
     }
+    execute!(io::stdout(), DisableFocusChange, Show)?;
+    Ok(())
+}
 
-    let mode = match args.mode {
-        Mode {
-            words: Some(words), ..
-        } => TestMode::Words(words),
-        Mode {
-            duration: Some(duration),
-            ..
-        } => TestMode::Duration(Duration::from_secs(duration)),
-        _ => TestMode::Duration(Duration::from_secs(30)),
-    };
+/// Words in the challenge of the day.
+const DAILY_WORDS: usize = 25;
 
-    let app = App {
-        state: Some(Box::new(TypingState::new(word_list.words.clone(), mode))),
-        word_list,
-    };
+/// The options of the challenge of the day, with the built in words.
+fn daily_options() -> LastRun {
+    LastRun {
+        words: Some(DAILY_WORDS),
+        punctuate: true,
+        ..Default::default()
+    }
+}
 
-    let res = run_app(&mut terminal, app);
+/// The words of a test in the order they are typed. The list is kept as loaded,
+/// only the words of the test are shuffled and punctuated.
+fn test_words(word_list: &WordList, options: &LastRun, in_order: bool, seed: u64) -> Vec<String> {
+    match in_order {
+        true => word_list.words.clone(),
+        false => generate_words(
+            word_list,
+            seed,
+            &WordOptions {
+                shuffle: !options.no_shuffle,
+                capitalize: options.capitalize,
+                punctuate: options.punctuate,
+                difficulty: options.difficulty,
+            },
+        ),
+    }
+}
 
-    // restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+/// The words of a text from a file or stdin.
+fn read_text(path: &str) -> Result<WordList> {
+    if path == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text).context("couldn't read stdin")?;
+        return Ok(WordList::from_text("stdin", &text)?);
+    }
+    let text = fs::read_to_string(path).with_context(|| format!("couldn't read {path}"))?;
+    Ok(WordList::from_text(path, &text)?)
+}
 
-    if let Err(err) = res {
-        println!("{err:?}");
+#[cfg(feature = "clipboard")]
+fn read_clipboard() -> Result<WordList> {
+    let text = arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .context("couldn't read the clipboard")?;
+    Ok(WordList::from_text("clipboard", &text)?)
+}
+
+/// Replays the inputs of a recording without a terminal and prints the stats
+/// of the run, for checking the calculations from scripts.
+fn print_headless(path: &str) -> Result<()> {
+    let recording = Recording::load(path).with_context(|| format!("couldn't load the run from {path}"))?;
+    let mut state = TypingState::new(recording.words, recording.mode);
+    state.lenient_case = recording.lenient_case;
+    for &(at, input) in &recording.inputs {
+        state.apply_at(input, at);
     }
-    println!("seed:");
-    println!("{}", seed);
+    // a words test lasts until its last input
+    let test_duration = match recording.mode {
+        TestMode::Duration(duration) => duration,
+        TestMode::Words(_) | TestMode::Chars(_) => recording.inputs.last().map_or(Duration::ZERO, |&(at, _)| at),
+    };
+    let stats = FinalStats::calculate(&state.written_words, &state.word_list, test_duration, &recording.mode);
+    let output = serde_json::json!({
+        "mode": recording.mode.to_string(),
+        "duration": test_duration.as_secs_f64(),
+        "stats": stats,
+        "accuracy": calculate_accuracy(state.key_strokes()),
+        "rhythm": typing_rhythm(state.key_strokes()).map(|rhythm| rhythm.to_string()),
+        "backspaces": count_backspaces(state.key_strokes()),
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
     Ok(())
 }
 
-fn run_app(terminal: &mut Terminal<states::Backend>, mut app: App) -> io::Result<()> {
-    loop {
-        terminal.draw(|f| ui(f, &mut app))?;
+fn print_leaderboard(mode: Option<TestMode>) {
+    let entries = history::load();
+    let best = history::leaderboard(&entries, mode, 10);
+    if best.is_empty() {
+        println!("no runs in the history yet");
+        return;
+    }
+    println!("{:>2}  {:>5}  {:>5}  {:<10}  mode", "#", "wpm", "acc", "date");
+    for (i, entry) in best.iter().enumerate() {
+        println!(
+            "{:>2}  {:>5.0}  {:>4.0}%  {:<10}  {}",
+            i + 1,
+            entry.wpm,
+            entry.accuracy * 100.0,
+            history::format_day(entry.day()),
+            entry.mode
+        );
+    }
+}
+
+fn print_stats_summary() {
+    let Some(summary) = history::summary(&history::load()) else {
+        println!("no runs in the history yet");
+        return;
+    };
+    println!("tests:            {}", summary.tests);
+    println!("average wpm:      {:.0}", summary.average_wpm);
+    println!("best wpm:         {:.0}", summary.best_wpm);
+    println!("average accuracy: {:.0}%", summary.average_accuracy * 100.0);
+    for (title, counts) in [("modes", summary.modes), ("languages", summary.languages)] {
+        println!("\n{title}:");
+        let width = counts.keys().map(String::len).max().unwrap_or_default();
+        for (name, count) in counts {
+            println!("  {name:<width$}  {count}");
+        }
+    }
+}
+
+const FRAME_TIME: Duration = Duration::from_millis(16);
+const IDLE_POLL_TIMEOUT: Duration = Duration::from_millis(250);
 
-        if let Some(Event::Key(key)) = event::poll(Duration::from_millis(16))
-            .and_then(|polled| polled.then(event::read).transpose())?
-        {
-            if handle_event(key, &mut app) {
-                break;
+/// Set by SIGINT or SIGTERM, `run_app` stops on the next frame.
+static QUIT: AtomicBool = AtomicBool::new(false);
+
+/// Runs until quitting, the state at that point is returned.
+fn run_app(
+    terminal: &mut Terminal<states::Backend>,
+    app: &mut App,
+    events: &mut impl EventSource,
+) -> io::Result<Box<dyn State>> {
+    let mut last_key = Instant::now();
+    let mut dirty = true;
+    while !QUIT.load(Ordering::Relaxed) {
+        let state = app.state.as_ref().unwrap();
+        if app.idle_timeout.is_some_and(|timeout| last_key.elapsed() > timeout) && state.can_idle() {
+            log_event!(info, "quitting after being idle");
+            break;
+        }
+        if let Some(opponent) = &mut app.opponent {
+            opponent.poll();
+            // the opponent's progress can change at any time
+            dirty = true;
+        }
+        let animating = state.animating();
+        if dirty || animating {
+            let drawing = Instant::now();
+            terminal.draw(|f| ui(f, app))?;
+            if drawing.elapsed() > FRAME_TIME {
+                log_event!(debug, "slow frame, drawing took {:?}", drawing.elapsed());
             }
-            app.state = Some(app.state.take().unwrap().handle_event(key, &mut app))
+            dirty = false;
         }
-        app.state = Some(app.state.take().unwrap().update(&mut app))
+
+        // nothing has to happen between key presses when nothing moves
+        let timeout = match animating || app.opponent.is_some() {
+            true => FRAME_TIME,
+            false => IDLE_POLL_TIMEOUT,
+        };
+        if let Some(event) = events.next_event(timeout)? {
+            if let Event::Key(key) = event {
+                last_key = Instant::now();
+                if handle_event(key, app) {
+                    log_event!(info, "quitting with {:?}", key.code);
+                    break;
+                }
+            }
+            app.state = Some(app.state.take().unwrap().handle_event(event, app));
+            dirty = true;
+        }
+        // an animating state can end on its own, the next state is drawn at least once
+        dirty |= app.state.as_ref().unwrap().animating();
+        app.state = Some(app.state.take().unwrap().update(app))
     }
 
-    Ok(())
+    if QUIT.load(Ordering::Relaxed) {
+        log_event!(info, "quitting on a signal");
+    }
+    Ok(app.state.take().unwrap())
 }
 
 fn handle_event(key: event::KeyEvent, _app: &mut App) -> bool {
@@ -225,3 +818,125 @@ fn ui(f: &mut Frame<states::Backend>, app: &mut App) {
         app.state = Some(state)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use crossterm::event::KeyEvent;
+    use ratatui::{TerminalOptions, Viewport};
+
+    use super::*;
+    use crate::events::ScriptedEvents;
+
+    /// Held by the tests that point the config dir at a temporary one.
+    static CONFIG_DIR: Mutex<()> = Mutex::new(());
+
+    fn test_app(words: &[&str], mode: TestMode) -> App {
+        let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        App {
+            word_list: WordList::new("test", words.clone()),
+            options: LastRun {
+                no_shuffle: true,
+                ..Default::default()
+            },
+            in_order: false,
+            seed: 0,
+            daily: None,
+            state: Some(Box::new(TypingState::new(words, mode))),
+            opponent: None,
+            export: None,
+            chart_smoothing: 1,
+            chart_points: 10,
+            unit: SpeedUnit::Wpm,
+            idle_timeout: None,
+            loop_after: None,
+            accuracy_formula: AccuracyFormula::Keystrokes,
+            required_accuracy: None,
+            #[cfg(feature = "sound")]
+            sound: None,
+            #[cfg(feature = "svg")]
+            chart_out: None,
+        }
+    }
+
+    /// Runs `app` with `script` typed as keys, `<` is backspace, `~` the terminal
+    /// getting focus and the script ends with Esc. The rows of the screen at
+    /// the end are returned.
+    fn run_script(app: &mut App, script: &str) -> Vec<String> {
+        // finished runs are saved to the history, keep it out of the real config
+        let _lock = CONFIG_DIR.lock().unwrap_or_else(|err| err.into_inner());
+        let config_dir = test_util::TempPath::new("session");
+        std::env::set_var("XDG_CONFIG_HOME", &*config_dir);
+
+        let area = Rect::new(0, 0, 100, 60);
+        let backend = CrosstermBackend::new(Box::new(io::sink()) as Box<dyn io::Write>);
+        let mut terminal = Terminal::with_options(backend, TerminalOptions { viewport: Viewport::Fixed(area) }).unwrap();
+        let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        let events = script
+            .chars()
+            .map(|c| match c {
+                '<' => key(KeyCode::Backspace),
+                '~' => Event::FocusGained,
+                c => key(KeyCode::Char(c)),
+            })
+            .chain([key(KeyCode::Esc)]);
+        let state = run_app(&mut terminal, app, &mut ScriptedEvents(events)).unwrap();
+        drop(config_dir);
+
+        let mut state = Some(state);
+        let frame = terminal.draw(|f| state.as_mut().unwrap().render(f, app)).unwrap();
+        let buf = frame.buffer;
+        (0..area.height)
+            .map(|y| (0..area.width).map(|x| buf.get(x, y).symbol.as_str()).collect())
+            .collect()
+    }
+
+    fn assert_shown(rows: &[String], expected: &str) {
+        assert!(rows.iter().any(|row| row.contains(expected)), "no {expected:?} in\n{}", rows.join("\n"));
+    }
+
+    #[test]
+    fn type_words_test() {
+        let mut app = test_app(&["ab", "cd"], TestMode::Words(2));
+        let rows = run_script(&mut app, "ab cx<d ");
+        // the run ended on the stats of the typed words
+        for expected in ["words: 2", "4/5 keystrokes", "correct:   4", "incorrect: 0", "0.00s ab"] {
+            assert_shown(&rows, expected);
+        }
+    }
+
+    #[test]
+    fn daily_challenge() {
+        let words = |day| test_words(WordList::built_in(), &daily_options(), false, daily_seed(day));
+        assert_eq!(words(20_000), words(20_000));
+        assert_ne!(words(20_000), words(20_001));
+
+        let mut app = test_app(&["ab", "cd"], TestMode::Words(DAILY_WORDS));
+        app.daily = Some(20_000);
+        let rows = run_script(&mut app, "a");
+        assert_shown(&rows, "daily 2024-10-04");
+    }
+
+    #[test]
+    fn loop_restarts_test() {
+        let mut app = test_app(&["ab", "cd"], TestMode::Words(2));
+        app.loop_after = Some(Duration::from_secs(3600));
+        let rows = run_script(&mut app, "ab cd ");
+        assert_shown(&rows, "next test");
+
+        // a key keeps the results on screen
+        let mut app = test_app(&["ab", "cd"], TestMode::Words(2));
+        app.loop_after = Some(Duration::from_secs(3600));
+        let rows = run_script(&mut app, "ab cd x");
+        assert_shown(&rows, "correct:   4");
+        assert!(!rows.iter().any(|row| row.contains("next test")));
+
+        // the next test starts once the results have been shown long enough
+        let mut app = test_app(&["ab", "cd"], TestMode::Words(2));
+        app.loop_after = Some(Duration::ZERO);
+        let rows = run_script(&mut app, "ab cd ~");
+        assert_shown(&rows, "test | 2 words");
+        assert_shown(&rows, "Start Typing to begin.");
+    }
+}
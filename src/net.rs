@@ -0,0 +1,208 @@
+//! Racing another player over TCP.
+//!
+//! Both sides send [`Message`]s as json, one per line. The host picks the words
+//! and sends them in a [`Message::Start`], after that both send their progress.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    analysis::TestMode,
+    error::{CrabtypeError, Result},
+    words::LineBreak,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    Start {
+        seed: u64,
+        /// seeds the words that are added during a timed test
+        #[serde(default)]
+        pool_seed: u64,
+        mode: TestMode,
+        words: Vec<String>,
+        #[serde(default)]
+        line_breaks: Vec<LineBreak>,
+    },
+    Progress {
+        /// amount of finished words
+        words: usize,
+        wpm: f64,
+    },
+    Finished {
+        wpm: f64,
+    },
+}
+
+/// A connection to the other player. Incoming messages are read on a separate
+/// thread so checking for them never blocks.
+pub struct Connection {
+    stream: TcpStream,
+    incoming: Receiver<Message>,
+}
+
+impl Connection {
+    /// Waits for one player to join.
+    pub fn host(addr: impl ToSocketAddrs) -> Result<Self> {
+        let (stream, _) = TcpListener::bind(addr)?.accept()?;
+        Self::new(stream)
+    }
+
+    pub fn join(addr: impl ToSocketAddrs) -> Result<Self> {
+        Self::new(TcpStream::connect(addr)?)
+    }
+
+    fn new(stream: TcpStream) -> Result<Self> {
+        let reader = BufReader::new(stream.try_clone()?);
+        let (sender, incoming) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                // a broken line or a closed channel both end the connection
+                let Some(message) = line.ok().and_then(|line| serde_json::from_str(&line).ok())
+                else {
+                    break;
+                };
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self { stream, incoming })
+    }
+
+    pub fn send(&self, message: &Message) -> Result<()> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        (&self.stream).write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Blocks until the next message arrives.
+    pub fn recv(&self) -> Result<Message> {
+        match self.incoming.recv() {
+            Ok(message) => Ok(message),
+            Err(_) => Err(CrabtypeError::Disconnected),
+        }
+    }
+
+    /// The next message if one has arrived, `Err` once the other player is gone.
+    pub fn try_recv(&self) -> Result<Option<Message>> {
+        match self.incoming.try_recv() {
+            Ok(message) => Ok(Some(message)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(CrabtypeError::Disconnected),
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // the reading thread has its own handle to the socket, so it has to be closed explicitly
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// What we know about the other player's run.
+pub struct Opponent {
+    connection: Connection,
+    pub words: usize,
+    pub wpm: f64,
+    pub finished: bool,
+    pub connected: bool,
+}
+
+impl Opponent {
+    pub fn new(connection: Connection) -> Self {
+        Self {
+            connection,
+            words: 0,
+            wpm: 0.0,
+            finished: false,
+            connected: true,
+        }
+    }
+
+    /// Applies every message that has arrived since the last call.
+    pub fn poll(&mut self) {
+        while self.connected {
+            match self.connection.try_recv() {
+                Ok(Some(Message::Progress { words, wpm })) => {
+                    self.words = words;
+                    self.wpm = wpm;
+                }
+                Ok(Some(Message::Finished { wpm })) => {
+                    self.wpm = wpm;
+                    self.finished = true;
+                }
+                Ok(Some(Message::Start { .. })) => (),
+                Ok(None) => break,
+                Err(_) => self.connected = false,
+            }
+        }
+    }
+
+    /// Sends a message, a lost connection is noticed by [`Opponent::poll`].
+    pub fn send(&self, message: &Message) {
+        if self.connected {
+            let _ = self.connection.send(message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn message_format() {
+        let message = Message::Progress { words: 3, wpm: 60.0 };
+        assert_eq!(
+            serde_json::to_string(&message).unwrap(),
+            r#"{"type":"progress","words":3,"wpm":60.0}"#
+        );
+    }
+
+    #[test]
+    fn race_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let joiner = thread::spawn(move || Connection::join(addr).unwrap());
+        let host = Connection::new(listener.accept().unwrap().0).unwrap();
+        let joiner = joiner.join().unwrap();
+
+        let start = Message::Start {
+            seed: 1,
+            pool_seed: 2,
+            mode: TestMode::Words(2),
+            words: vec!["a".to_string(), "b".to_string()],
+            line_breaks: vec![LineBreak { word: 1, indent: 2 }],
+        };
+        host.send(&start).unwrap();
+        assert_eq!(joiner.recv().unwrap(), start);
+
+        let mut opponent = Opponent::new(host);
+        joiner.send(&Message::Progress { words: 1, wpm: 42.0 }).unwrap();
+        joiner.send(&Message::Finished { wpm: 50.0 }).unwrap();
+        drop(joiner);
+        for _ in 0..100 {
+            opponent.poll();
+            if !opponent.connected {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(opponent.words, 1);
+        assert_eq!(opponent.wpm, 50.0);
+        assert!(opponent.finished);
+        assert!(!opponent.connected);
+    }
+}
@@ -0,0 +1,67 @@
+//! Recording the input of a run so it can be watched again.
+
+use std::{fs, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{analysis::TestMode, error::Result};
+
+/// An edit to the typed text.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Char(char),
+    Space,
+    Backspace,
+    DeleteWord,
+}
+
+/// Everything needed to replay a run: the words, the mode and the timed inputs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Recording {
+    pub mode: TestMode,
+    pub words: Vec<String>,
+    /// time since the start of the test and the input
+    pub inputs: Vec<(Duration, Input)>,
+    /// whether the run was typed with `--lenient-case`, older recordings don't have it
+    #[serde(default)]
+    pub lenient_case: bool,
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempPath;
+    #[test]
+    fn save_and_load() {
+        let path = TempPath::new("replay.json");
+        let recording = Recording {
+            mode: TestMode::Words(1),
+            words: vec!["ab".to_string()],
+            inputs: vec![
+                (Duration::from_millis(0), Input::Char('a')),
+                (Duration::from_millis(120), Input::Backspace),
+                (Duration::from_millis(300), Input::Space),
+            ],
+            lenient_case: true,
+        };
+        recording.save(&path).unwrap();
+        assert_eq!(Recording::load(&path).unwrap(), recording);
+    }
+
+    #[test]
+    fn recording_without_lenient_case() {
+        let recording: Recording = serde_json::from_str(r#"{"mode": {"Words": 1}, "words": ["ab"], "inputs": []}"#).unwrap();
+        assert!(!recording.lenient_case);
+    }
+}
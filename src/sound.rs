@@ -0,0 +1,33 @@
+use std::io::Cursor;
+
+use crabtype::analysis::KeyStrokeKind;
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Source};
+
+const CLICK: &[u8] = include_bytes!("../sounds/click.wav");
+const ERROR: &[u8] = include_bytes!("../sounds/error.wav");
+
+/// Plays key sounds on rodio's own output thread, so playing never blocks the render loop.
+pub struct Sound {
+    // the stream stops playing when it's dropped
+    _stream: OutputStream,
+    handle: OutputStreamHandle,
+}
+
+impl Sound {
+    /// `None` when there's no audio device.
+    pub fn new() -> Option<Self> {
+        let (_stream, handle) = OutputStream::try_default().ok()?;
+        Some(Self { _stream, handle })
+    }
+
+    pub fn play(&self, key_stroke: &KeyStrokeKind) {
+        let sample = match key_stroke {
+            KeyStrokeKind::Incorrect(_) => ERROR,
+            _ => CLICK,
+        };
+        if let Ok(decoder) = Decoder::new(Cursor::new(sample)) {
+            // a missed click isn't worth stopping the test for
+            let _ = self.handle.play_raw(decoder.convert_samples());
+        }
+    }
+}
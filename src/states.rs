@@ -1,20 +1,43 @@
-use std::io::Stdout;
+use std::io::Write;
 
-use crossterm::event;
+use crossterm::event::Event;
 use ratatui::{Frame, prelude::CrosstermBackend};
 
 use crate::App;
 
 pub trait State {
-    fn handle_event(self: Box<Self>, event: event::KeyEvent, app: &App) -> Box<dyn State>;
+    /// Gets every terminal event, not only key presses.
+    fn handle_event(self: Box<Self>, event: Event, app: &App) -> Box<dyn State>;
     fn update(self: Box<Self>, app: &App) -> Box<dyn State>;
     fn render(&mut self, f: &mut Frame<Backend>, app: &App);
+    /// Whether the screen changes without any events, e.g. a running timer.
+    /// Otherwise it's only redrawn after events.
+    fn animating(&self) -> bool {
+        false
+    }
+    /// Whether `--idle-timeout` may quit now, not while a test is running.
+    fn can_idle(&self) -> bool {
+        true
+    }
+    /// The results in one line for `--inline`, once a test is finished.
+    fn results_line(&self, _app: &App) -> Option<String> {
+        None
+    }
+    /// Called once crabtype quits and the terminal is restored.
+    fn quit(self: Box<Self>) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
 
-pub type Backend = CrosstermBackend<Stdout>;
+/// Writes to stdout, boxed so tests can draw into a sink instead.
+pub type Backend = CrosstermBackend<Box<dyn Write>>;
 mod typing;
 pub use typing::*;
 mod stats;
 pub use stats::*;
+mod replay;
+pub use replay::*;
+mod editor;
+pub use editor::*;
 
 
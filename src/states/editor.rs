@@ -0,0 +1,183 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+
+use crate::App;
+
+use super::{Backend, State};
+use crabtype::words::{is_typable, WordList};
+
+/// Adds and removes the words of a word list file, which is saved when crabtype quits.
+pub struct EditorState {
+    path: String,
+    word_list: WordList,
+    list_state: ListState,
+    /// the word being typed, added to the list with enter
+    input: String,
+    changed: bool,
+    /// don't print where the list was saved
+    pub quiet: bool,
+}
+
+impl EditorState {
+    /// Opens the list at `path`, or starts a new one named after the file.
+    pub fn open(path: &str) -> Result<Self> {
+        let word_list = match Path::new(path).exists() {
+            true => WordList::load(Some(path))?,
+            false => {
+                let name = Path::new(path).file_stem().map_or(path.into(), |stem| stem.to_string_lossy());
+                WordList::new(&name, Vec::new())
+            }
+        };
+        let mut list_state = ListState::default();
+        list_state.select(word_list.words.len().checked_sub(1));
+        Ok(Self {
+            path: path.to_string(),
+            word_list,
+            list_state,
+            input: String::new(),
+            changed: false,
+            quiet: false,
+        })
+    }
+
+    fn edit(&mut self, key: KeyEvent) {
+        let len = self.word_list.words.len();
+        let selected = self.list_state.selected();
+        match key.code {
+            KeyCode::Char(c) if is_typable(c) && !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.input.push(c)
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Enter if !self.input.is_empty() => {
+                self.word_list.words.push(std::mem::take(&mut self.input));
+                self.list_state.select(Some(len));
+                self.changed = true;
+            }
+            KeyCode::Delete => {
+                if let Some(i) = selected {
+                    self.word_list.remove_word(i);
+                    self.list_state.select(match len - 1 {
+                        0 => None,
+                        left => Some(i.min(left - 1)),
+                    });
+                    self.changed = true;
+                }
+            }
+            KeyCode::Up => self.list_state.select(selected.map(|i| i.saturating_sub(1))),
+            KeyCode::Down => self.list_state.select(selected.map(|i| (i + 1).min(len - 1))),
+            KeyCode::Home if len > 0 => self.list_state.select(Some(0)),
+            KeyCode::End if len > 0 => self.list_state.select(Some(len - 1)),
+            _ => {}
+        }
+    }
+}
+
+impl State for EditorState {
+    fn handle_event(mut self: Box<Self>, event: Event, _app: &App) -> Box<dyn State> {
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press {
+                self.edit(key);
+            }
+        }
+        self
+    }
+    fn update(self: Box<Self>, _app: &App) -> Box<dyn State> {
+        self
+    }
+    fn render(&mut self, f: &mut Frame<Backend>, _app: &App) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Min(0),
+                Constraint::Length(3),
+                Constraint::Length(1),
+            ])
+            .margin(1)
+            .split(f.size());
+        let title = format!("{} | {} words | {}", self.word_list.title(), self.word_list.words.len(), self.path);
+        f.render_widget(
+            Paragraph::new(title)
+                .style(Style::default().dark_gray())
+                .alignment(Alignment::Center),
+            layout[0],
+        );
+        let words = List::new(
+            self.word_list
+                .words
+                .iter()
+                .map(|word| ListItem::new(word.as_str()))
+                .collect::<Vec<_>>(),
+        )
+        .highlight_style(Style::default().yellow().bold())
+        .highlight_symbol("> ");
+        f.render_stateful_widget(words, layout[1], &mut self.list_state);
+        f.render_widget(
+            Paragraph::new(self.input.as_str()).block(Block::default().borders(Borders::ALL).title("new word")),
+            layout[2],
+        );
+        f.render_widget(
+            Paragraph::new("enter: add | delete: remove | up/down: select | esc: save and quit")
+                .style(Style::default().dark_gray()),
+            layout[3],
+        );
+    }
+    fn quit(self: Box<Self>) -> Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+        if self.word_list.words.is_empty() {
+            bail!("'{}' wasn't saved, a word list needs words", self.path);
+        }
+        self.word_list.save(&self.path)?;
+        if !self.quiet {
+            println!("saved {} words to {}", self.word_list.words.len(), self.path);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempPath;
+
+    fn press(editor: &mut EditorState, keys: &str) {
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\x7f' => KeyCode::Delete,
+                '\x08' => KeyCode::Backspace,
+                c => KeyCode::Char(c),
+            };
+            editor.edit(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+    }
+
+    #[test]
+    fn edit_words() {
+        let path = TempPath::new("editor.json");
+        let mut editor = EditorState::open(path.to_str().unwrap()).unwrap();
+        assert_eq!(editor.word_list.name, format!("crabtype-{}-editor", std::process::id()));
+        press(&mut editor, "one\ntwoo\x08\n \nthree\n");
+        assert_eq!(editor.word_list.words, ["one", "two", "three"]);
+        assert_eq!(editor.list_state.selected(), Some(2));
+
+        editor.edit(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+        press(&mut editor, "\x7f");
+        assert_eq!(editor.word_list.words, ["one", "three"]);
+        assert_eq!(editor.list_state.selected(), Some(1));
+        press(&mut editor, "\x7f\x7f\x7f");
+        assert!(editor.word_list.words.is_empty());
+        assert_eq!(editor.list_state.selected(), None);
+        assert!(Box::new(editor).quit().is_err());
+    }
+}
@@ -0,0 +1,62 @@
+use std::{collections::VecDeque, time::Duration, time::Instant};
+
+use crossterm::event::Event;
+use crabtype::replay::{Input, Recording};
+use ratatui::Frame;
+
+use crate::App;
+
+use super::{Backend, State, TypingState};
+
+/// Plays back a recorded run at its original speed. Key presses are ignored.
+pub struct ReplayState {
+    typing: Box<TypingState>,
+    inputs: VecDeque<(Duration, Input)>,
+    start_time: Instant,
+}
+
+impl ReplayState {
+    pub fn new(recording: Recording) -> Self {
+        let start_time = Instant::now();
+        let mut typing = TypingState::new(recording.words, recording.mode);
+        typing.lenient_case = recording.lenient_case;
+        typing.start(start_time);
+        Self {
+            typing: Box::new(typing),
+            inputs: recording.inputs.into(),
+            start_time,
+        }
+    }
+}
+
+impl State for ReplayState {
+    fn handle_event(self: Box<Self>, _event: Event, _app: &App) -> Box<dyn State> {
+        self
+    }
+    fn update(mut self: Box<Self>, app: &App) -> Box<dyn State> {
+        let elapsed = self.start_time.elapsed();
+        while self.inputs.front().is_some_and(|(time, _)| *time <= elapsed) {
+            let (_, input) = self.inputs.pop_front().unwrap();
+            self.typing.apply(input, self.start_time);
+        }
+        // a replay isn't a new run, so it isn't saved to the history
+        match self.typing.finished() {
+            Some(test_duration) => Box::new(
+                self.typing
+                    .into_stats(test_duration, app.chart_points)
+                    .accuracy_formula(app.accuracy_formula)
+                    .require_accuracy(app.required_accuracy),
+            ),
+            None => self,
+        }
+    }
+    fn animating(&self) -> bool {
+        true
+    }
+    fn can_idle(&self) -> bool {
+        false
+    }
+    fn render(&mut self, f: &mut Frame<Backend>, app: &App) {
+        self.typing.render(f, app)
+    }
+}
@@ -1,93 +1,70 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crossterm::event;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
 use ratatui::{
     prelude::*,
-    widgets::{Axis, Chart, Dataset, GraphType, List, ListItem},
+    widgets::{
+        Axis, Chart, Dataset, GraphType, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation,
+        ScrollbarState,
+    },
 };
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 use crate::App;
 
-use super::{Backend, KeyStrokeKind, State, TestMode};
+use super::{Backend, State, TypingState};
+use crabtype::{
+    analysis::{
+        calculate_accuracy, calculate_accuracy_with, count_backspaces, normalize_wpm, typing_rhythm, word_difference,
+        Accuracy, AccuracyFormula, CharDiffKind, FinalStats, KeyStrokeKind, Rhythm, SpeedUnit, TestMode,
+    },
+    history::{self, HistoryEntry},
+};
 
-use itertools::{EitherOrBoth, Itertools};
+use itertools::Itertools;
 
 pub struct StatsState {
     raw_wpms: Vec<(f64, f64)>,
     //correct_wpms: Vec<(f64, f64)>,
     errors_wpms: Vec<(f64, f64)>,
-    accuracy: f64,
+    accuracy: Accuracy,
+    accuracy_formula: AccuracyFormula,
+    key_strokes: Vec<(Duration, KeyStrokeKind)>,
+    rhythm: Option<Rhythm>,
     test_duration: Duration,
     final_stats: FinalStats,
     mode: TestMode,
+    /// the slowest words with how long they took, slowest first
+    slowest_words: Vec<(String, Duration)>,
+    /// the accuracy in percent the run needed but didn't reach
+    failed: Option<u8>,
+    /// (day streak, tests finished today), known once the run is saved to the history
+    streak: Option<(u32, usize)>,
+    /// the last run of the same mode before this one, from the history
+    previous: Option<HistoryEntry>,
+    /// the typed words colored letter by letter, shown instead of the chart when reviewing
+    review: Vec<Vec<Span<'static>>>,
+    reviewing: bool,
+    /// backspaces pressed in each word of the review
+    corrections: Vec<u32>,
+    /// highlight the words of the review that were corrected
+    show_corrections: bool,
+    /// first row of the review that is shown
+    scroll: usize,
+    /// rows of the review that didn't fit on the screen when it was last drawn
+    max_scroll: usize,
+    /// the unit picked on this screen, otherwise the one of the options
+    unit: Option<SpeedUnit>,
+    /// the seed of the words when it isn't the one of the app
+    seed: Option<u64>,
+    /// the test started when the results have been shown long enough, see `--loop`
+    next_test: Option<(Instant, Box<TypingState>)>,
+    /// where the chart was saved to, or why it couldn't be
+    #[cfg(feature = "svg")]
+    chart_saved: Option<String>,
 }
 
-#[derive(PartialEq, Clone, Debug)]
-struct FinalStats {
-    wpm: f64,     // amount of characters in fully correct words + spaces normalized
-    raw_wpm: f64, // wpm with incorrect words' characters
-    correct: u32,
-    incorrect: u32,
-    extra: u32,
-    missed: u32,
-}
-
-impl Default for FinalStats {
-    fn default() -> Self {
-        Self {
-            wpm: 0.0,
-            raw_wpm: 0.0,
-            correct: 0,
-            incorrect: 0,
-            extra: 0,
-            missed: 0,
-        }
-    }
-}
-
-impl FinalStats {
-    fn calculate(
-        inputted_words: &[String],
-        correct_words: &[String],
-        test_duration: Duration, //for normalizing wpm
-    ) -> Self {
-        let mut result = inputted_words
-            .iter()
-            .zip(correct_words.iter())
-            .enumerate()
-            .fold(Self::default(), |mut acc, (i, (input, correct))| {
-                if input == correct {
-                    acc.wpm += input.len() as f64 + 1.0
-                } else if i == inputted_words.len() - 1
-                    && input.len() <= correct.len()
-                    && input == &correct[0..input.len()]
-                {
-                    acc.wpm += input.len() as f64;
-                    acc.raw_wpm -= 1.0;
-                }
-                acc.raw_wpm += input.len() as f64 + 1.0;
-                for d in word_difference(
-                    if i != inputted_words.len() - 1 {
-                        &correct
-                    } else {
-                        &correct[0..input.len().min(correct.len())]
-                    },
-                    &input,
-                ) {
-                    match d {
-                        CharDiffKind::Correct => acc.correct += 1,
-                        CharDiffKind::Incorrect => acc.incorrect += 1,
-                        CharDiffKind::Extra => acc.extra += 1,
-                        CharDiffKind::Missed => acc.missed += 1,
-                    }
-                }
-                acc
-            });
-        result.wpm = normalize_wpm(result.wpm, test_duration.as_secs_f64());
-        result.raw_wpm = normalize_wpm(result.raw_wpm, test_duration.as_secs_f64());
-        result
-    }
-}
 impl StatsState {
     pub fn new(
         key_strokes: Vec<(Duration, KeyStrokeKind)>,
@@ -95,8 +72,9 @@ impl StatsState {
         inputted_words: &[String],
         correct_words: &[String],
         mode: TestMode,
+        chart_points: usize,
     ) -> Self {
-        let time_step = (test_duration.as_secs_f64() / 20.0).max(0.5);
+        let time_step = chart_time_step(test_duration, chart_points);
         let batched_ks = batch_key_strokes(&key_strokes, time_step);
 
         Self {
@@ -109,37 +87,265 @@ impl StatsState {
                 .filter_map(|t| (t.2 != 0.0).then_some((t.0, normalize_wpm(t.2, time_step))))
                 .collect_vec(),
             accuracy: calculate_accuracy(&key_strokes),
+            accuracy_formula: AccuracyFormula::default(),
+            rhythm: typing_rhythm(&key_strokes),
+            corrections: review_corrections(&key_strokes, inputted_words),
             test_duration,
-            final_stats: FinalStats::calculate(inputted_words, correct_words, test_duration),
+            final_stats: FinalStats::calculate(
+                inputted_words,
+                correct_words,
+                test_duration,
+                &mode,
+            ),
+            key_strokes,
             mode,
+            slowest_words: Vec::new(),
+            failed: None,
+            streak: None,
+            previous: None,
+            review: review_words(inputted_words, correct_words),
+            show_corrections: false,
+            reviewing: false,
+            scroll: 0,
+            max_scroll: 0,
+            unit: None,
+            seed: None,
+            next_test: None,
+            #[cfg(feature = "svg")]
+            chart_saved: None,
         }
     }
 
-    fn render_stats(&self, f: &mut Frame<'_, Backend>, area: Rect) {
-        let stats = [           
+    pub fn wpm(&self) -> f64 {
+        self.final_stats.wpm
+    }
+
+    /// Keeps the slowest of the words typed in `word_times` to show them.
+    pub fn word_times(mut self, mut word_times: Vec<(String, Duration)>) -> Self {
+        word_times.sort_by(|(_, l), (_, r)| r.cmp(l));
+        word_times.truncate(SLOWEST_WORDS);
+        self.slowest_words = word_times;
+        self
+    }
+
+    /// Saves the chart to the svg file of `--chart-out`, as it's shown at first.
+    #[cfg(feature = "svg")]
+    pub fn save_chart(mut self, app: &App) -> Self {
+        let Some(path) = &app.chart_out else {
+            return self;
+        };
+        let in_unit = |series: &[(f64, f64)]| {
+            series
+                .iter()
+                .map(|&(time, wpm)| (time, app.unit.from_wpm(wpm)))
+                .collect_vec()
+        };
+        let result = crate::chart::save_svg(
+            path,
+            &in_unit(&moving_average(&self.raw_wpms, app.chart_smoothing)),
+            &in_unit(&self.errors_wpms),
+            app.unit.into(),
+        );
+        self.chart_saved = Some(match result {
+            Ok(()) => format!("saved to {path}"),
+            Err(err) => format!("{err:#}"),
+        });
+        self
+    }
+
+    /// Calculates the accuracy with `formula` instead of from every keystroke.
+    pub fn accuracy_formula(mut self, formula: AccuracyFormula) -> Self {
+        self.accuracy = calculate_accuracy_with(formula, &self.key_strokes, &self.final_stats);
+        self.accuracy_formula = formula;
+        self
+    }
+
+    /// Fails the run if its accuracy is below `min` percent.
+    pub fn require_accuracy(mut self, min: Option<u8>) -> Self {
+        self.failed = min.filter(|&min| self.accuracy.accuracy * 100.0 < min as f64);
+        self
+    }
+
+    pub fn seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Starts `next_test` once the results have been shown for `delay`.
+    pub fn restart_after(mut self, delay: Duration, next_test: TypingState) -> Self {
+        self.next_test = Some((Instant::now() + delay, Box::new(next_test)));
+        self
+    }
+
+    /// Appends this run to the history and looks up the current streak and the
+    /// run to compare to, failed runs aren't saved. `language` is the one of the word list.
+    pub fn save_to_history(mut self, language: Option<String>) -> Self {
+        if self.failed.is_some() {
+            return self;
+        }
+        let entry = HistoryEntry {
+            timestamp: history::now(),
+            mode: self.mode,
+            wpm: self.final_stats.wpm,
+            raw_wpm: self.final_stats.raw_wpm,
+            accuracy: self.accuracy.accuracy,
+            language,
+        };
+        let mut entries = history::load();
+        self.previous = history::previous(&entries, self.mode).cloned();
+        // the results are still shown even if they can't be saved
+        if history::append(&entry).is_ok() {
+            let today = entry.day();
+            entries.push(entry);
+            self.streak = Some((
+                history::streak(&entries, today),
+                history::sessions_on(&entries, today),
+            ));
+        }
+        self
+    }
+
+    /// e.g. `62 wpm | 97% acc | 65 raw | words:25`, with `failed` when the run failed.
+    fn compact_results(&self, unit: SpeedUnit) -> String {
+        let mut results = vec![
+            format!("{:.0} {unit}", unit.from_wpm(self.final_stats.wpm)),
+            format!("{:.0}% acc", self.accuracy.accuracy * 100.0),
+            format!("{:.0} raw", unit.from_wpm(self.final_stats.raw_wpm)),
+            self.mode.to_string(),
+        ];
+        if self.failed.is_some() {
+            results.push(FAILED.to_string());
+        }
+        results.join(" | ")
+    }
+
+    fn render_stats(&self, f: &mut Frame<'_, Backend>, area: Rect, app: &App, unit: SpeedUnit) {
+        let mut stats = vec![
             match self.mode {
                 TestMode::Duration(s) => ("mode", format!("duration: {}s", s.as_secs())),
                 TestMode::Words(w) => ("mode", format!("words: {w}")),
+                TestMode::Chars(c) => ("mode", format!("chars: {c}")),
             },
-            ("wpm", format!("{:.0}", self.final_stats.wpm)),
-            ("raw", format!("{:.0}", self.final_stats.raw_wpm)),
-            ("acc", format!("{:.0}%", self.accuracy * 100.0)),
+            (
+                "words",
+                [
+                    Some(app.word_list.title()),
+                    app.word_list.author.as_ref().map(|author| format!("by {author}")),
+                    app.word_list.description.clone(),
+                ]
+                .into_iter()
+                .flatten()
+                .join("\n"),
+            ),
+            (unit.into(), format!("{:.0}", unit.from_wpm(self.final_stats.wpm))),
+            ("raw", format!("{:.0}", unit.from_wpm(self.final_stats.raw_wpm))),
+            {
+                let (peak, average) = peak_and_average(&self.raw_wpms);
+                (
+                    "interval",
+                    format!(
+                        "peak:    {:.0}\naverage: {:.0}",
+                        unit.from_wpm(peak),
+                        unit.from_wpm(average)
+                    ),
+                )
+            },
+            (
+                "acc",
+                format!(
+                    "{:.0}%\n{}/{} {}",
+                    self.accuracy.accuracy * 100.0,
+                    self.accuracy.correct,
+                    self.accuracy.correct + self.accuracy.incorrect,
+                    match self.accuracy_formula {
+                        AccuracyFormula::Keystrokes => "keystrokes",
+                        AccuracyFormula::Typed => "keys",
+                        AccuracyFormula::Text => "letters",
+                    }
+                ),
+            ),
             (
                 "chars",
                 format!(
-                    "correct:   {}\nincorrect: {}\nextra:     {}\nmissed:    {}",
+                    "correct:   {}\nincorrect: {}\ncase:      {}\nextra:     {}\nmissed:    {}\nfixed:     {}",
                     self.final_stats.correct,
                     self.final_stats.incorrect,
+                    self.final_stats.case_errors,
                     self.final_stats.extra,
-                    self.final_stats.missed
+                    self.final_stats.missed,
+                    count_backspaces(&self.key_strokes)
                 ),
             ),
         ];
-        let t = stats.map(|(name, value)| {
+        if let Some(previous) = &self.previous {
+            stats.push(("vs last", compare_to(&self.final_stats, &self.accuracy, previous, unit)));
+        }
+        if let Some(rhythm) = self.rhythm {
+            stats.push(("rhythm", rhythm.to_string()));
+        }
+        if !self.slowest_words.is_empty() {
+            stats.push((
+                "slowest words",
+                self.slowest_words
+                    .iter()
+                    .map(|(word, time)| format!("{:.2}s {word}", time.as_secs_f64()))
+                    .join("\n"),
+            ));
+        }
+        if let Some(min) = self.failed {
+            stats.insert(0, (FAILED, format!("accuracy under {min}%\nnot saved")));
+        }
+        if let Some((streak, sessions)) = self.streak {
+            stats.push((
+                "streak",
+                format!("🔥 {streak} day streak\n{sessions} tests today"),
+            ));
+        }
+        if let Some(opponent) = &app.opponent {
+            stats.push((
+                "opponent",
+                match (opponent.connected, opponent.finished) {
+                    (_, true) => format!("{:.0} {unit}", unit.from_wpm(opponent.wpm)),
+                    (true, false) => format!("{:.0} {unit}\nstill typing", unit.from_wpm(opponent.wpm)),
+                    (false, false) => "disconnected".to_string(),
+                },
+            ));
+        }
+        #[cfg(feature = "svg")]
+        if let Some(saved) = &self.chart_saved {
+            stats.push(("chart", saved.clone()));
+        }
+        stats.push(("seed", self.seed.unwrap_or(app.seed).to_string()));
+        if let Some((at, _)) = &self.next_test {
+            let left = at.saturating_duration_since(Instant::now());
+            stats.push((
+                "next test",
+                format!("in {}s\nany key to stay", left.as_secs_f64().ceil()),
+            ));
+        }
+        stats.push((
+            "keys",
+            format!(
+                "tab: {}\nu:   show {}{}",
+                match self.reviewing {
+                    true => "chart",
+                    false => "review",
+                },
+                unit.next(),
+                match self.reviewing {
+                    true => "\nc:   corrections",
+                    false => "",
+                }
+            ),
+        ));
+        let t = stats.into_iter().map(|(name, value)| {
             ListItem::new({
                 let mut it = vec![Line::from(Span::styled(
                     name.to_string(),
-                    Style::default().yellow(),
+                    match name {
+                        FAILED => Style::default().red().bold(),
+                        _ => Style::default().yellow(),
+                    },
                 ))];
                 for row in value.split('\n') {
                     it.push(Line::from(Span::raw(row.to_string())))
@@ -148,42 +354,113 @@ impl StatsState {
                 it
             })
         });
-        let list = List::new(t.to_vec());
+        let list = List::new(t.collect_vec());
         f.render_widget(list, area)
     }
 
-    fn render_chart(&mut self, f: &mut Frame<'_, Backend>, area: Rect) {
-        let max_wpm = (self
-            .raw_wpms
+    /// Moves the review by `rows`, without scrolling past its first or last row.
+    fn scroll_by(&mut self, rows: isize) {
+        self.scroll = self.scroll.saturating_add_signed(rows).min(self.max_scroll);
+    }
+
+    fn render_review(&mut self, f: &mut Frame<'_, Backend>, area: Rect) {
+        let height = area.height as usize;
+        let mut rows = wrap_words(&self.review, area.width as usize);
+        // the last column is left for the scrollbar when the text doesn't fit
+        let overflows = rows.len() > height;
+        if overflows {
+            rows = wrap_words(&self.review, area.width.saturating_sub(1) as usize);
+        }
+        let content_length = rows.len();
+        self.max_scroll = content_length.saturating_sub(height);
+        self.scroll = self.scroll.min(self.max_scroll);
+        let lines = rows
+            .into_iter()
+            .skip(self.scroll)
+            .map(|words| {
+                Line::from(
+                    words
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(n, &i)| {
+                            let corrected = self.show_corrections && self.corrections[i] > 0;
+                            (n > 0).then(|| Span::raw(" ")).into_iter().chain(self.review[i].iter().map(move |span| {
+                                match corrected {
+                                    true => Span::styled(span.content.clone(), span.style.on_dark_gray()),
+                                    false => span.clone(),
+                                }
+                            }))
+                        })
+                        .collect_vec(),
+                )
+            })
+            .collect_vec();
+        f.render_widget(Paragraph::new(lines), area);
+        if overflows {
+            let mut scrollbar_state = ScrollbarState::default()
+                .content_length(self.max_scroll as u16 + 1)
+                .viewport_content_length(area.height)
+                .position(self.scroll as u16);
+            f.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                    .begin_symbol(None)
+                    .end_symbol(None)
+                    .style(Style::default().dark_gray()),
+                area,
+                &mut scrollbar_state,
+            );
+        }
+    }
+
+    fn render_chart(&mut self, f: &mut Frame<'_, Backend>, area: Rect, smoothing: usize, unit: SpeedUnit) {
+        let in_unit = |series: Vec<(f64, f64)>| {
+            series
+                .into_iter()
+                .map(|(time, wpm)| (time, unit.from_wpm(wpm)))
+                .collect_vec()
+        };
+        let raw_wpms = in_unit(decimate(&moving_average(&self.raw_wpms, smoothing), MAX_CHART_POINTS));
+        let errors_wpms = in_unit(decimate(&self.errors_wpms, MAX_CHART_POINTS));
+        // a label every 40 wpm, or as many characters
+        let step = unit.from_wpm(40.0) as usize;
+        let max_wpm = (raw_wpms
             .iter()
             .max_by(|l, r| l.1.total_cmp(&r.1))
             .map(|&(_, wpm)| wpm as usize)
             .unwrap_or(0)
-            / 40
+            / step
             + 1)
-            * 40;
+            * step;
 
-        let last_time = self.test_duration.as_secs_f64();
+        // fit the axis to the data, with a small margin so the last point isn't drawn on the edge
+        let last_time = self
+            .raw_wpms
+            .iter()
+            .map(|&(time, _)| time)
+            .reduce(f64::max)
+            .unwrap_or(self.test_duration.as_secs_f64())
+            .max(0.1)
+            * 1.05;
 
         let chart = Chart::new(vec![
             Dataset::default()
                 .graph_type(GraphType::Line)
-                .data(&self.raw_wpms[0..])
+                .data(&raw_wpms)
                 .marker(symbols::Marker::Braille)
                 .style(Style::default().fg(Color::DarkGray)),
             Dataset::default()
                 .graph_type(GraphType::Scatter)
-                .data(&self.errors_wpms[0..])
+                .data(&errors_wpms)
                 .marker(symbols::Marker::Bar)
                 .style(Style::default().fg(Color::Red)),
         ])
         .y_axis(
             Axis::default()
                 .bounds([0f64, max_wpm as f64])
-                .title("wpm")
+                .title(<&str>::from(unit))
                 .labels(
                     (0..=max_wpm)
-                        .step_by(40)
+                        .step_by(step)
                         .map(|i| Span::from(format!("{}", i)))
                         .collect(),
                 )
@@ -191,12 +468,15 @@ impl StatsState {
         )
         .x_axis(
             Axis::default()
-                .bounds([1f64, last_time])
+                .bounds([0f64, last_time])
                 .title("time (s)")
                 .labels(
-                    [1.0, (last_time / 2.0).round(), last_time.round()]
+                    [0.0, last_time / 2.0, last_time]
                         .iter()
-                        .map(|i| Span::from(format!("{i}")))
+                        .map(|i| match last_time < 10.0 {
+                            true => Span::from(format!("{i:.1}")),
+                            false => Span::from(format!("{i:.0}")),
+                        })
                         .collect(),
                 ),
         );
@@ -205,71 +485,201 @@ impl StatsState {
 }
 
 impl State for StatsState {
-    fn handle_event(self: Box<Self>, _event: event::KeyEvent, _app: &App) -> Box<dyn State> {
+    fn handle_event(mut self: Box<Self>, event: Event, app: &App) -> Box<dyn State> {
+        let Event::Key(key) = event else {
+            return self;
+        };
+        if key.kind != KeyEventKind::Press {
+            return self;
+        }
+        // the results stay on screen once anything is done with them
+        self.next_test = None;
+        match key.code {
+            KeyCode::Tab => self.reviewing = !self.reviewing,
+            KeyCode::Char('c') => self.show_corrections = !self.show_corrections,
+            KeyCode::Char('u') => self.unit = Some(self.unit.unwrap_or(app.unit).next()),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll_by(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll_by(1),
+            KeyCode::PageUp => self.scroll_by(-(REVIEW_PAGE as isize)),
+            KeyCode::PageDown => self.scroll_by(REVIEW_PAGE as isize),
+            KeyCode::Home => self.scroll = 0,
+            KeyCode::End => self.scroll = self.max_scroll,
+            _ => {}
+        }
         self
     }
-    fn update(self: Box<Self>, _app: &App) -> Box<dyn State> {
-        self
+    fn update(mut self: Box<Self>, _app: &App) -> Box<dyn State> {
+        match self.next_test.take() {
+            Some((at, next_test)) if Instant::now() >= at => {
+                log_event!(info, "results shown long enough, starting the next test");
+                next_test
+            }
+            next_test => {
+                self.next_test = next_test;
+                self
+            }
+        }
     }
-    fn render(&mut self, f: &mut Frame<Backend>, _app: &App) {
+    fn animating(&self) -> bool {
+        // the countdown to the next test moves
+        self.next_test.is_some()
+    }
+    fn results_line(&self, app: &App) -> Option<String> {
+        Some(self.compact_results(self.unit.unwrap_or(app.unit)))
+    }
+    fn render(&mut self, f: &mut Frame<Backend>, app: &App) {
         let layout = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints(vec![Constraint::Max(17), Constraint::Min(0)])
+            .constraints(vec![Constraint::Max(20), Constraint::Min(0)])
             .split(f.size());
-        self.render_chart(f, layout[1]);
-        self.render_stats(f, layout[0]);
+        let unit = self.unit.unwrap_or(app.unit);
+        match self.reviewing {
+            true => self.render_review(f, layout[1]),
+            false => self.render_chart(f, layout[1], app.chart_smoothing, unit),
+        }
+        self.render_stats(f, layout[0], app, unit);
     }
 }
 
-fn normalize_wpm(char_amount: f64, time: f64) -> f64 {
-    char_amount / 5.0 * (60.0 / time)
+
+/// Amount of words shown as the slowest.
+const SLOWEST_WORDS: usize = 5;
+
+/// Rows scrolled by PageUp and PageDown in the review.
+const REVIEW_PAGE: usize = 10;
+
+/// Title of the stats entry of a failed run, shown in red.
+const FAILED: &str = "failed";
+
+/// Every typed word with its letters colored like while typing, the letters
+/// left out of the last word aren't shown as missed since it wasn't finished.
+fn review_words(inputted_words: &[String], correct_words: &[String]) -> Vec<Vec<Span<'static>>> {
+    let last = inputted_words.len().saturating_sub(1);
+    inputted_words
+        .iter()
+        .zip(correct_words)
+        .enumerate()
+        .filter(|(_, (input, _))| !input.is_empty())
+        .map(|(i, (input, correct))| {
+            let mut typed = input.graphemes(true);
+            let mut expected = correct.graphemes(true);
+            word_difference(correct, input)
+                .filter_map(|dif| {
+                    let (letter, style) = match dif {
+                        CharDiffKind::Correct => (typed.next().zip(expected.next())?.0, Style::default().green()),
+                        CharDiffKind::Incorrect => (typed.next().zip(expected.next())?.0, Style::default().red()),
+                        CharDiffKind::CaseError => (typed.next().zip(expected.next())?.0, Style::default().yellow()),
+                        CharDiffKind::Extra => (typed.next()?, Style::default().red().dim()),
+                        CharDiffKind::Missed if i == last => return None,
+                        CharDiffKind::Missed => (expected.next()?, Style::default().dark_gray().underlined()),
+                    };
+                    Some(Span::styled(letter.to_string(), style))
+                })
+                .collect()
+        })
+        .collect()
 }
 
-#[allow(unused)]
-#[derive(PartialEq, Clone, Debug)]
-pub enum CharDiffKind {
-    Correct,
-    Incorrect,
-    Extra,
-    Missed,
+/// The speed and accuracy of a run relative to `previous`, e.g. `+4 wpm, -1% acc`.
+fn compare_to(final_stats: &FinalStats, accuracy: &Accuracy, previous: &HistoryEntry, unit: SpeedUnit) -> String {
+    format!(
+        "{:+.0} {unit}, {:+.0}% acc",
+        unit.from_wpm(final_stats.wpm) - unit.from_wpm(previous.wpm),
+        (accuracy.accuracy - previous.accuracy) * 100.0
+    )
 }
 
-pub fn word_difference<'a>(
-    correct_word: &'a str,
-    input: &'a str,
-) -> impl Iterator<Item = CharDiffKind> + 'a {
-    correct_word
-        .chars()
-        .zip_longest(input.chars())
-        .map(|e| match e {
-            EitherOrBoth::Left(_) => CharDiffKind::Missed,
-            EitherOrBoth::Right(_) => CharDiffKind::Extra,
-            EitherOrBoth::Both(c, i) => {
-                if c == i {
-                    CharDiffKind::Correct
-                } else {
-                    CharDiffKind::Incorrect
-                }
+/// How many times backspace or Ctrl+W was pressed in each word of the
+/// review, which leaves out the words that are empty.
+fn review_corrections(key_strokes: &[(Duration, KeyStrokeKind)], inputted_words: &[String]) -> Vec<u32> {
+    let mut corrections = vec![0; inputted_words.len()];
+    for (_, ks) in key_strokes {
+        if let KeyStrokeKind::Backspace(word) = ks {
+            if let Some(count) = corrections.get_mut(*word) {
+                *count += 1;
             }
-        })
+        }
+    }
+    corrections
+        .into_iter()
+        .zip(inputted_words)
+        .filter(|(_, input)| !input.is_empty())
+        .map(|(count, _)| count)
+        .collect()
 }
 
-fn calculate_accuracy(key_strokes: &[(Duration, KeyStrokeKind)]) -> f64 {
-    let mut correct = 0.0;
-    let mut incorrect = 0.0;
-    for (_, ks) in key_strokes.iter() {
-        match ks {
-            KeyStrokeKind::Correct(_) => correct += 1.0,
-            KeyStrokeKind::Incorrect(_) => incorrect += 1.0,
-            KeyStrokeKind::Space(i) if i != &0 => incorrect += 1.0,
-            _ => (),
+/// Splits words into rows at most `width` columns wide, with a space between
+/// words, as indices into `words`. A word wider than a row gets a row of its own.
+fn wrap_words(words: &[Vec<Span>], width: usize) -> Vec<Vec<usize>> {
+    let mut rows: Vec<Vec<usize>> = Vec::new();
+    let mut x = 0;
+    for (i, word) in words.iter().enumerate() {
+        let word_width: usize = word.iter().map(|span| span.content.width()).sum();
+        match rows.last_mut() {
+            Some(row) if x + 1 + word_width <= width => {
+                row.push(i);
+                x += 1 + word_width;
+            }
+            _ => {
+                rows.push(vec![i]);
+                x = word_width;
+            }
         }
     }
-    correct / (correct + incorrect)
+    rows
+}
+
+/// The highest and the average value of a chart series, zeros when it's empty.
+fn peak_and_average(series: &[(f64, f64)]) -> (f64, f64) {
+    if series.is_empty() {
+        return (0.0, 0.0);
+    }
+    let peak = series.iter().map(|&(_, value)| value).fold(0.0, f64::max);
+    let sum: f64 = series.iter().map(|&(_, value)| value).sum();
+    (peak, sum / series.len() as f64)
+}
+
+/// Averages every point with the `window - 1` points before it, a window of 0 or 1 changes nothing.
+fn moving_average(series: &[(f64, f64)], window: usize) -> Vec<(f64, f64)> {
+    let window = window.max(1);
+    series
+        .iter()
+        .enumerate()
+        .map(|(i, &(time, _))| {
+            let points = &series[(i + 1).saturating_sub(window)..=i];
+            let sum: f64 = points.iter().map(|&(_, value)| value).sum();
+            (time, sum / points.len() as f64)
+        })
+        .collect()
+}
+
+/// Most points drawn in the chart, no matter how many `--chart-points` asks for.
+const MAX_CHART_POINTS: usize = 500;
+
+/// Thins a series out to at most `max` points, keeping the highest point of every
+/// run of consecutive points so peaks aren't lost.
+fn decimate(series: &[(f64, f64)], max: usize) -> Vec<(f64, f64)> {
+    if series.len() <= max {
+        return series.to_vec();
+    }
+    series
+        .chunks(series.len().div_ceil(max.max(1)))
+        .filter_map(|chunk| chunk.iter().copied().max_by(|l, r| l.1.total_cmp(&r.1)))
+        .collect()
 }
 
-//kinda breaks when the duration is 0 but that rarely (never) happens so its ok :)
-fn batch_key_strokes(
+/// Default amount of points in the wpm chart.
+pub const CHART_POINTS: usize = 20;
+
+/// Size of a chart bucket in seconds, aims for `points` points but no less than half a second,
+/// and keeps at least 2 points for tests that last only a second or two.
+fn chart_time_step(test_duration: Duration, points: usize) -> f64 {
+    let secs = test_duration.as_secs_f64();
+    (secs / points.max(1) as f64).max(0.5).min(secs / 2.0).max(0.05)
+}
+
+// time_step has to be positive, chart_time_step() makes sure of that
+pub(super) fn batch_key_strokes(
     key_strokes: &[(Duration, KeyStrokeKind)],
     time_step: f64,
 ) -> Vec<(f64, f64, f64)> {
@@ -285,6 +695,8 @@ fn batch_key_strokes(
                     acc.1 += 1.0;
                     acc.0 += 1.0
                 }
+                // a backspace doesn't type anything
+                KeyStrokeKind::Backspace(_) => {}
                 _ => acc.0 += 1.0,
             }
             acc
@@ -298,18 +710,6 @@ fn batch_key_strokes(
 mod tests {
     use super::*;
     #[test]
-    fn word_dif_extra() {
-        use CharDiffKind::*;
-        assert!(word_difference("aabbc", "ahhbcaa")
-            .eq([Correct, Incorrect, Incorrect, Correct, Correct, Extra, Extra]))
-    }
-    #[test]
-    fn word_dif_missed() {
-        use CharDiffKind::*;
-        assert!(word_difference("bbbdas", "bbb")
-            .eq([Correct, Correct, Correct, Missed, Missed, Missed]))
-    }
-    #[test]
     fn batch_ks() {
         use KeyStrokeKind::*;
         let stats = [
@@ -347,82 +747,228 @@ mod tests {
         )
     }
     #[test]
-    fn final_stats_empty() {
-        let stats = FinalStats::calculate(&[], &[], Duration::from_secs(60));
-        assert_eq!(stats, FinalStats::default())
+    fn stats_state_keeps_mode() {
+        use KeyStrokeKind::*;
+        let key_strokes = [(0.1, Correct('a')), (0.3, Correct('b')), (0.5, Space(0))]
+            .map(|(d, ks)| (Duration::from_secs_f64(d), ks))
+            .to_vec();
+        let input = ["ab", ""].map(String::from);
+        let correct = ["ab"].map(String::from);
+        let state = StatsState::new(
+            key_strokes,
+            Duration::from_secs_f64(0.5),
+            &input,
+            &correct,
+            TestMode::Words(1),
+            CHART_POINTS,
+        );
+        assert_eq!(state.mode, TestMode::Words(1));
+        assert_eq!(state.final_stats.correct, 2);
+        assert_eq!(state.final_stats.missed, 0);
     }
     #[test]
-    fn final_stats_all_correct() {
-        let input = ["dac", "b"].map(String::from);
-        let correct = ["dac", "bb"].map(String::from);
-        let stats = FinalStats::calculate(
+    fn short_test_chart() {
+        use KeyStrokeKind::*;
+        let key_strokes = [
+            (0.1, Correct('a')),
+            (0.2, Correct('b')),
+            (0.3, Space(0)),
+            (0.45, Correct('c')),
+            (0.6, Correct('d')),
+        ]
+        .map(|(d, ks)| (Duration::from_secs_f64(d), ks))
+        .to_vec();
+        let input = ["ab", "cd"].map(String::from);
+        let correct = ["ab", "cd"].map(String::from);
+        let state = StatsState::new(
+            key_strokes,
+            Duration::from_secs_f64(0.7),
             &input,
             &correct,
-            Duration::from_secs(
-                12, /* 12 to make char amount match wpm due to how normalize_wpm() works : x/5 * (60/12) = x  */
-            ),
+            TestMode::Words(2),
+            CHART_POINTS,
         );
-        assert_eq!(
-            stats,
-            FinalStats {
-                wpm: 5.0, // 4 chars + 1 space
-                raw_wpm: 5.0,
-                correct: 4,
-                extra: 0,
-                incorrect: 0,
-                missed: 0,
-            }
-        )
+        assert_eq!(chart_time_step(Duration::from_secs_f64(0.7), CHART_POINTS), 0.35);
+        assert_eq!(state.raw_wpms.len(), 2);
+    }
+    #[test]
+    fn corrections_per_word() {
+        use KeyStrokeKind::*;
+        let key_strokes = [Correct('a'), Backspace(0), Correct('a'), Space(0), Backspace(1), Backspace(2)]
+            .map(|ks| (Duration::ZERO, ks))
+            .to_vec();
+        let input = ["a", "b", ""].map(String::from);
+        assert_eq!(review_corrections(&key_strokes, &input), [1, 1]);
     }
     #[test]
-    fn final_stats_errors() {
-        let input = ["bbc", "bda", "cdq", "a"].map(String::from);
-        let correct = ["dac", "bb", "cd", "aaa"].map(String::from);
-        let stats = FinalStats::calculate(&input, &correct, Duration::from_secs(12));
+    fn stats_no_key_strokes() {
+        let input = [String::new()];
+        let correct = ["abc"].map(String::from);
+        let state = StatsState::new(
+            Vec::new(),
+            Duration::ZERO,
+            &input,
+            &correct,
+            TestMode::Duration(Duration::ZERO),
+            CHART_POINTS,
+        );
+        assert!(state.raw_wpms.is_empty());
+        assert!(state.errors_wpms.is_empty());
+        assert_eq!(state.accuracy.accuracy, 0.0);
+        assert_eq!(state.final_stats, FinalStats::default());
+    }
+    #[test]
+    fn moving_average_window() {
+        let series = [(1.0, 10.0), (2.0, 20.0), (3.0, 60.0), (4.0, 10.0)];
+        assert_eq!(moving_average(&series, 0), series);
+        assert_eq!(moving_average(&series, 1), series);
         assert_eq!(
-            stats,
-            FinalStats {
-                wpm: 1.0,
-                raw_wpm: 13.0,
-                correct: 5,
-                extra: 2,
-                incorrect: 3,
-                missed: 0,
-            }
-        )
+            moving_average(&series, 2),
+            [(1.0, 10.0), (2.0, 15.0), (3.0, 40.0), (4.0, 35.0)]
+        );
+        assert_eq!(
+            moving_average(&series, 3),
+            [(1.0, 10.0), (2.0, 15.0), (3.0, 30.0), (4.0, 30.0)]
+        );
+    }
+    #[test]
+    fn decimate_keeps_peaks() {
+        let series: Vec<_> = (0..1000).map(|i| (i as f64, (i % 10) as f64)).collect();
+        let decimated = decimate(&series, 100);
+        assert_eq!(decimated.len(), 100);
+        assert!(decimated.iter().all(|&(_, wpm)| wpm == 9.0));
+        assert_eq!(decimate(&series[..50], 100), &series[..50]);
+        assert!(decimate(&series, 7).len() <= 7);
+    }
+    #[test]
+    fn required_accuracy() {
+        use KeyStrokeKind::*;
+        let key_strokes = [(0.1, Correct('a')), (0.2, Incorrect('x')), (0.3, Correct('c'))]
+            .map(|(d, ks)| (Duration::from_secs_f64(d), ks))
+            .to_vec();
+        let input = ["axc"].map(String::from);
+        let correct = ["abc"].map(String::from);
+        let state = || {
+            StatsState::new(
+                key_strokes.clone(),
+                Duration::from_secs(1),
+                &input,
+                &correct,
+                TestMode::Words(1),
+                CHART_POINTS,
+            )
+        };
+        assert_eq!(state().require_accuracy(None).failed, None);
+        assert_eq!(state().require_accuracy(Some(60)).failed, None);
+        assert_eq!(state().require_accuracy(Some(90)).failed, Some(90));
+        // not saved, so there's no streak
+        assert_eq!(state().require_accuracy(Some(90)).save_to_history(None).streak, None);
+    }
+    #[test]
+    fn compare_to_previous_run() {
+        let input = ["abc", "def", ""].map(String::from);
+        let state = StatsState::new(Vec::new(), Duration::from_secs(12), &input, &input, TestMode::Words(2), CHART_POINTS);
+        assert_eq!(state.final_stats.wpm, 9.0);
+        let previous = HistoryEntry {
+            timestamp: 0,
+            mode: TestMode::Words(2),
+            wpm: 13.0,
+            raw_wpm: 13.0,
+            accuracy: 0.9,
+            language: None,
+        };
+        let accuracy = Accuracy { correct: 95, incorrect: 5, accuracy: 0.95 };
+        assert_eq!(compare_to(&state.final_stats, &accuracy, &previous, SpeedUnit::Wpm), "-4 wpm, +5% acc");
+        assert_eq!(compare_to(&state.final_stats, &accuracy, &previous, SpeedUnit::Cpm), "-20 cpm, +5% acc");
+    }
+    #[test]
+    fn compact_results_line() {
+        use KeyStrokeKind::*;
+        let key_strokes = [(0.1, Correct('a')), (0.2, Incorrect('x')), (0.3, Correct('c'))]
+            .map(|(d, ks)| (Duration::from_secs_f64(d), ks))
+            .to_vec();
+        let input = ["axc", ""].map(String::from);
+        let correct = ["abc"].map(String::from);
+        let state = StatsState::new(key_strokes, Duration::from_secs(12), &input, &correct, TestMode::Words(1), CHART_POINTS);
+        assert_eq!(state.compact_results(SpeedUnit::Wpm), "0 wpm | 67% acc | 4 raw | words:1");
+        let state = state.require_accuracy(Some(90));
+        assert_eq!(state.compact_results(SpeedUnit::Cpm), "0 cpm | 67% acc | 20 raw | words:1 | failed");
     }
     #[test]
-    fn final_stats_missed() {
-        let input = ["bb", "b", "ha", "b"].map(String::from);
-        let correct = ["bbaa", "baaa", "haaa", "b"].map(String::from);
-        let stats = FinalStats::calculate(&input, &correct, Duration::from_secs(12));
+    fn review_scroll_stays_in_the_text() {
+        let input = ["a"].map(String::from);
+        let mut state = StatsState::new(
+            Vec::new(),
+            Duration::from_secs(1),
+            &input,
+            &input,
+            TestMode::Words(1),
+            CHART_POINTS,
+        );
+        state.max_scroll = 15;
+        state.scroll_by(-1);
+        assert_eq!(state.scroll, 0);
+        state.scroll_by(REVIEW_PAGE as isize);
+        state.scroll_by(REVIEW_PAGE as isize);
+        assert_eq!(state.scroll, 15);
+        state.scroll_by(-(REVIEW_PAGE as isize));
+        assert_eq!(state.scroll, 5);
+    }
+    #[test]
+    fn slowest_words() {
+        let input = ["a"].map(String::from);
+        let state = StatsState::new(
+            Vec::new(),
+            Duration::from_secs(1),
+            &input,
+            &input,
+            TestMode::Words(1),
+            CHART_POINTS,
+        );
+        let word_times = (1..=7).map(|i| (i.to_string(), Duration::from_millis(i * 100))).collect();
+        let state = state.word_times(word_times);
         assert_eq!(
-            stats,
-            FinalStats {
-                wpm: 2.0,
-                raw_wpm: 10.0,
-                correct: 6,
-                extra: 0,
-                incorrect: 0,
-                missed: 7,
-            }
-        )
+            state.slowest_words.iter().map(|(word, _)| word.as_str()).collect_vec(),
+            ["7", "6", "5", "4", "3"]
+        );
     }
     #[test]
-    fn final_stats_duration() {
-        let input = ["aaaa", "aaaa", "aaaa", "aaaa"].map(String::from);
-        let correct = ["aaaa", "aaaa", "aaaa", "aaaa"].map(String::from);
-        let stats = FinalStats::calculate(&input, &correct, Duration::from_secs(60));
+    fn review_colors() {
+        let input = ["helo", "worldd", "fo"].map(String::from);
+        let correct = ["hello", "world", "foo"].map(String::from);
+        let review = review_words(&input, &correct);
+        let styled = |word: &Vec<Span>| word.iter().map(|span| (span.content.to_string(), span.style.fg)).collect_vec();
         assert_eq!(
-            stats,
-            FinalStats {
-                wpm: 20.0 / 5.0, // 4 chars + 1 space
-                raw_wpm: 20.0 / 5.0,
-                correct: 16,
-                extra: 0,
-                incorrect: 0,
-                missed: 0,
-            }
-        )
+            styled(&review[0]),
+            [
+                ("h".to_string(), Some(Color::Green)),
+                ("e".to_string(), Some(Color::Green)),
+                ("l".to_string(), Some(Color::Green)),
+                ("o".to_string(), Some(Color::Red)),
+                ("o".to_string(), Some(Color::DarkGray)),
+            ]
+        );
+        assert_eq!(styled(&review[1]).last(), Some(&("d".to_string(), Some(Color::Red))));
+        // the unfinished last word has nothing missed
+        assert_eq!(review[2].len(), 2);
+
+        assert_eq!(wrap_words(&review, 11), [vec![0], vec![1, 2]]);
+        assert_eq!(wrap_words(&review, 3), [vec![0], vec![1], vec![2]]);
+        assert!(wrap_words(&[], 10).is_empty());
+    }
+    #[test]
+    fn peak_and_average_wpm() {
+        let series = [(1.0, 40.0), (2.0, 90.0), (3.0, 50.0)];
+        assert_eq!(peak_and_average(&series), (90.0, 60.0));
+        assert_eq!(peak_and_average(&[]), (0.0, 0.0));
+    }
+    #[test]
+    fn chart_points() {
+        let minute = Duration::from_secs(60);
+        assert_eq!(chart_time_step(minute, CHART_POINTS), 3.0);
+        assert_eq!(chart_time_step(minute, 60), 1.0);
+        assert_eq!(chart_time_step(minute, 1000), 0.5);
+        assert_eq!(chart_time_step(minute, 0), 30.0);
+        assert!(chart_time_step(Duration::ZERO, 1) > 0.0);
     }
 }
@@ -1,38 +1,160 @@
 use std::time::{Duration, Instant};
 
-use crossterm::event::{self, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 
-use crate::{typingwidget::TypingWidget, App};
+use crate::{bigtext, typingwidget::TypingWidget, App};
 
-use super::{Backend, State, StatsState};
-use ratatui::{prelude::*, widgets::Gauge};
+use super::{stats::batch_key_strokes, Backend, State, StatsState};
+use crabtype::{
+    analysis::{
+        calculate_accuracy, grapheme_len, is_case_mismatch, normalize_wpm, same_grapheme, words_match,
+        FinalStats, KeyStrokeKind, TestMode,
+    },
+    history,
+    net::{Message, Opponent},
+    replay::{Input, Recording},
+    words::{is_typable, LineBreak, WordPool},
+};
+use rand::{rngs::SmallRng, SeedableRng};
+use ratatui::{
+    prelude::*,
+    widgets::{Gauge, Paragraph, Sparkline},
+};
+use unicode_segmentation::UnicodeSegmentation;
 
 pub struct TypingState {
     pub written_words: Vec<String>,
     start_time: Option<Instant>,
+    /// when the terminal lost focus, the clock stops until it's back
+    paused_at: Option<Instant>,
     pub rows: Vec<usize>,
     pub word_list: Vec<String>,
+    /// indices of the words that always start a new line, see `WordList::line_breaks`
+    pub line_breaks: Vec<LineBreak>,
     key_strokes: Vec<(Duration, KeyStrokeKind)>, //(time of keystroke, kind)
+    /// when the first letter of the word being typed was typed
+    word_started: Option<Duration>,
+    /// every finished word with the time from its first letter to the space after it
+    word_times: Vec<(String, Duration)>,
     mode: TestMode,
+    /// color the gauge by the accuracy of the last keystrokes instead of plain yellow
+    pub accuracy_gauge: bool,
+    /// show the time or words left in big digits below the text
+    pub big_timer: bool,
+    /// leave the row of the gauge empty once the test has started
+    pub hide_progress: bool,
+    /// show the wpm of the last seconds next to the gauge
+    pub sparkline: bool,
+    /// the time between two beats of the metronome flashing next to the gauge
+    pub metronome: Option<Duration>,
+    /// show the accuracy of the last keystrokes next to the gauge, green at or above this percentage
+    pub accuracy_goal: Option<u8>,
+    /// keystrokes typed in every second of the test, counted as they come in
+    key_strokes_per_second: Vec<f64>,
+    /// amount of `key_strokes` already counted in `key_strokes_per_second`
+    binned_key_strokes: usize,
+    pub text_position: TextPosition,
+    /// record letters typed in the wrong case as `WrongCase` instead of `Incorrect`
+    pub lenient_case: bool,
+    /// backspace can go back into correct words too, not only into ones with mistakes
+    pub free_backspace: bool,
+    /// flash the gap before the word being typed when backspace can't go back into a correct word
+    pub boundary_flash: bool,
+    /// when backspace or Ctrl+W last couldn't go back to the previous word
+    blocked_at: Option<Instant>,
+    /// lay the text out from right to left
+    pub rtl: bool,
+    /// what is drawn between the words
+    pub separator: Separator,
+    /// highlight the punctuation of the words that aren't typed yet
+    pub punctuation_hints: bool,
+    /// draw the word being typed in bold on top of the colors of its letters
+    pub active_word: bool,
+    /// dim the lines above the one with the word being typed
+    pub dim_completed_lines: bool,
+    /// only show the word being typed and this many after it
+    pub reveal: Option<usize>,
+    /// how long the finished text stays on screen before the stats
+    pub finish_hold: Duration,
+    /// when the test was finished and how long it lasted, while the text is held on screen
+    held: Option<(Instant, Duration)>,
+    /// when progress was last sent to the opponent
+    last_progress: Option<Instant>,
+    /// every input with its time, for exporting a replay
+    inputs: Vec<(Duration, Input)>,
+    /// more words for when a duration test gets close to the end of `word_list`
+    word_pool: Option<WordPool>,
+    /// the seed of the words when it isn't the one of the app, after `--loop` restarted
+    seed: Option<u64>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum KeyStrokeKind {
-    Correct(char),
-    Incorrect(char),
-    ///amount of extra letters in the word before, when n < 0, skipped letters
-    Space(i32),
+/// Where the text box is placed vertically.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextPosition {
+    /// a bit above the center
+    #[default]
+    Upper,
+    Center,
+    Top,
 }
 
-pub enum TestMode {
-    Duration(Duration),
-    Words(usize),
+impl TextPosition {
+    /// Rows above the text box in an area `height` rows tall.
+    fn offset(self, height: u16) -> u16 {
+        match self {
+            TextPosition::Upper => height * 3 / 10,
+            TextPosition::Center => height.saturating_sub(TEXT_BOX_HEIGHT) / 2,
+            TextPosition::Top => 0,
+        }
+    }
+}
+
+/// The gap drawn between words, they are still typed with a single space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Separator {
+    /// columns between two words
+    pub width: u16,
+    /// shown in the middle of the gap, e.g. `·`, nothing by default
+    pub glyph: Option<char>,
+}
+
+impl Default for Separator {
+    fn default() -> Self {
+        Self { width: 1, glyph: None }
+    }
 }
+
+const TEXT_BOX_HEIGHT: u16 = 3;
+
+/// How many words there should be left to type before more are taken from the pool.
+const WORDS_AHEAD: usize = 100;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Amount of keystrokes the accuracy of the gauge is calculated over.
+const GAUGE_ACCURACY_WINDOW: usize = 20;
+
+/// Seconds shown in the sparkline, one column each.
+const SPARKLINE_SECONDS: usize = 20;
+
+/// The sparkline is left out on terminals narrower than this.
+const SPARKLINE_MIN_WIDTH: u16 = 60;
+
+/// How long the word boundary flashes when backspace can't go past it.
+const BOUNDARY_FLASH: Duration = Duration::from_millis(150);
+
+/// Columns of the metronome at the end of the gauge row.
+const METRONOME_WIDTH: u16 = 2;
+
+/// Columns of the accuracy next to the gauge with `accuracy_goal`, fits ` 100%`.
+const ACCURACY_GOAL_WIDTH: u16 = 5;
+
 impl TypingState {
     pub fn new(mut word_list: Vec<String>, mode: TestMode) -> Self {
         Self {
             written_words: vec![String::new()],
             start_time: None,
+            paused_at: None,
             rows: vec![0],
             word_list: if let TestMode::Words(words) = mode {
                 word_list.resize(words, String::new());
@@ -40,136 +162,561 @@ impl TypingState {
             } else {
                 word_list
             },
+            line_breaks: Vec::new(),
             key_strokes: Vec::new(),
+            word_started: None,
+            word_times: Vec::new(),
             mode,
+            accuracy_gauge: true,
+            big_timer: false,
+            hide_progress: false,
+            sparkline: false,
+            metronome: None,
+            accuracy_goal: None,
+            key_strokes_per_second: Vec::new(),
+            binned_key_strokes: 0,
+            text_position: TextPosition::default(),
+            lenient_case: false,
+            free_backspace: false,
+            boundary_flash: true,
+            blocked_at: None,
+            rtl: false,
+            separator: Separator::default(),
+            punctuation_hints: false,
+            active_word: true,
+            dim_completed_lines: true,
+            reveal: None,
+            finish_hold: Duration::ZERO,
+            held: None,
+            last_progress: None,
+            inputs: Vec::new(),
+            word_pool: None,
+            seed: None,
+        }
+    }
+
+    /// A new test with the same mode and settings and newly generated words.
+    pub fn restarted(&self, app: &App) -> Self {
+        let seed = rand::random();
+        let words = app.test_words(seed);
+        let mut state = Self::new(words.clone(), self.mode);
+        if let TestMode::Duration(_) = self.mode {
+            state.refill_from(WordPool::new(words, SmallRng::seed_from_u64(seed), app.shuffle()));
         }
+        log_event!(info, "next test with seed {seed}");
+        state.seed = Some(seed);
+        state.line_breaks = self.line_breaks.clone();
+        state.accuracy_gauge = self.accuracy_gauge;
+        state.big_timer = self.big_timer;
+        state.hide_progress = self.hide_progress;
+        state.sparkline = self.sparkline;
+        state.metronome = self.metronome;
+        state.accuracy_goal = self.accuracy_goal;
+        state.text_position = self.text_position;
+        state.lenient_case = self.lenient_case;
+        state.free_backspace = self.free_backspace;
+        state.boundary_flash = self.boundary_flash;
+        state.rtl = self.rtl;
+        state.separator = self.separator;
+        state.punctuation_hints = self.punctuation_hints;
+        state.active_word = self.active_word;
+        state.dim_completed_lines = self.dim_completed_lines;
+        state.reveal = self.reveal;
+        state.finish_hold = self.finish_hold;
+        state
     }
 
-    fn remove_empty(&mut self) {
-        if self.written_words.len() > 1 {
-            if self.written_words[self.written_words.len() - 2]
-                != self.word_list[self.written_words.len() - 2]
-            {
-                self.written_words.pop();
+    /// Keeps a duration test from running out of words.
+    pub fn refill_from(&mut self, word_pool: WordPool) {
+        self.word_pool = Some(word_pool);
+        self.fill_words();
+    }
+
+    fn fill_words(&mut self) {
+        let Some(word_pool) = &mut self.word_pool else {
+            return;
+        };
+        while self.word_list.len() < self.written_words.len() + WORDS_AHEAD {
+            let batch = word_pool.next_batch();
+            if batch.is_empty() {
+                break;
             }
+            self.word_list.extend(batch);
         }
     }
 
-    fn remove_char(&mut self) {
-        let Some(last) = self.written_words.last_mut() else {
+    /// The length of the test once it's over.
+    pub fn finished(&self) -> Option<Duration> {
+        let elapsed = self.elapsed()?;
+        match self.mode {
+            TestMode::Duration(dur) => (elapsed > dur).then_some(dur),
+            TestMode::Words(words) => (self.written_words.len() > words).then_some(elapsed),
+            // a text shorter than the limit ends with its last word
+            TestMode::Chars(chars) => {
+                (self.typed_chars() >= chars || self.written_words.len() > self.word_list.len()).then_some(elapsed)
+            }
+        }
+    }
+
+    /// Typed characters, counting the spaces between the words.
+    fn typed_chars(&self) -> usize {
+        self.written_words.iter().map(|word| grapheme_len(word)).sum::<usize>() + self.written_words.len() - 1
+    }
+
+    /// The length of a finished test once it has been held on screen for
+    /// `finish_hold`, the hold starts the first time the test is found finished.
+    fn end_of_hold(&mut self, now: Instant) -> Option<Duration> {
+        let test_duration = self.finished()?;
+        let (since, test_duration) = *self.held.get_or_insert((now, test_duration));
+        (now.saturating_duration_since(since) >= self.finish_hold).then_some(test_duration)
+    }
+
+    /// Time since the start, without the time spent paused.
+    fn elapsed(&self) -> Option<Duration> {
+        let start_time = self.start_time?;
+        Some(self.paused_at.unwrap_or_else(Instant::now).saturating_duration_since(start_time))
+    }
+
+    /// Stops the clock of a started test.
+    pub fn pause(&mut self) {
+        if self.start_time.is_some() && self.paused_at.is_none() {
+            self.paused_at = Some(Instant::now());
+        }
+    }
+
+    /// Continues the clock from where it was paused, as if no time had passed.
+    pub fn resume(&mut self) {
+        if let (Some(start_time), Some(paused_at)) = (&mut self.start_time, self.paused_at.take()) {
+            *start_time += paused_at.elapsed();
+        }
+    }
+
+    pub fn into_stats(self, test_duration: Duration, chart_points: usize) -> StatsState {
+        StatsState::new(
+            self.key_strokes,
+            test_duration,
+            &self.written_words,
+            &self.word_list,
+            self.mode,
+            chart_points,
+        )
+        .word_times(self.word_times)
+        .seed(self.seed)
+    }
+
+    fn finish(self, test_duration: Duration, app: &App) -> Box<dyn State> {
+        log_event!(
+            info,
+            "test finished after {test_duration:?}, {} words and {} keystrokes",
+            self.written_words.len() - 1,
+            self.key_strokes.len()
+        );
+        let next_test = app.loop_after.map(|delay| (delay, self.restarted(app)));
+        if let Some(path) = &app.export {
+            let recording = Recording {
+                mode: self.mode,
+                words: self.word_list.clone(),
+                inputs: self.inputs.clone(),
+                lenient_case: self.lenient_case,
+            };
+            // the results are still shown even if the run can't be exported
+            let _ = recording.save(path);
+        }
+        let stats = self
+            .into_stats(test_duration, app.chart_points)
+            .accuracy_formula(app.accuracy_formula)
+            .require_accuracy(app.required_accuracy)
+            .save_to_history(app.word_list.language.clone());
+        #[cfg(feature = "svg")]
+        let stats = stats.save_chart(app);
+        if let Some(opponent) = &app.opponent {
+            opponent.send(&Message::Finished { wpm: stats.wpm() });
+        }
+        match next_test {
+            Some((delay, next_test)) => Box::new(stats.restart_after(delay, next_test)),
+            None => Box::new(stats),
+        }
+    }
+
+    /// Time since the start for a key stroke, never past the end of a duration
+    /// test even if the event loop lagged behind the deadline.
+    fn key_stroke_time(&self, start_time: Instant) -> Duration {
+        match self.mode {
+            TestMode::Duration(dur) => start_time.elapsed().min(dur),
+            TestMode::Words(_) | TestMode::Chars(_) => start_time.elapsed(),
+        }
+    }
+
+    /// The wpm of each of the last full seconds, oldest first, counting only
+    /// the keystrokes added since the last call.
+    fn recent_wpms(&mut self, elapsed: Duration) -> Vec<u64> {
+        for (time, chars, _) in batch_key_strokes(&self.key_strokes[self.binned_key_strokes..], 1.0) {
+            // batches end at `time`, so the one ending at 1.0 is the first second
+            let second = (time as usize).saturating_sub(1);
+            if self.key_strokes_per_second.len() <= second {
+                self.key_strokes_per_second.resize(second + 1, 0.0);
+            }
+            self.key_strokes_per_second[second] += chars;
+        }
+        self.binned_key_strokes = self.key_strokes.len();
+
+        let full_seconds = elapsed.as_secs() as usize;
+        (full_seconds.saturating_sub(SPARKLINE_SECONDS)..full_seconds)
+            .map(|second| {
+                let chars = self.key_strokes_per_second.get(second).copied().unwrap_or(0.0);
+                normalize_wpm(chars, 1.0) as u64
+            })
+            .collect()
+    }
+
+    pub fn key_strokes(&self) -> &[(Duration, KeyStrokeKind)] {
+        &self.key_strokes
+    }
+
+    /// Starts the clock without waiting for the first key press.
+    pub fn start(&mut self, time: Instant) {
+        self.start_time = Some(time);
+    }
+
+    /// Applies an input made now, in a test started at `start_time`.
+    pub fn apply(&mut self, input: Input, start_time: Instant) {
+        self.apply_at(input, self.key_stroke_time(start_time));
+    }
+
+    /// Applies an input made `at` after the start of the test, e.g. one of a recording.
+    pub fn apply_at(&mut self, input: Input, at: Duration) {
+        match input {
+            Input::Char(c) => self.add_char(c, at),
+            Input::Space => self.add_space(at),
+            // a backspace that's blocked or has nothing to remove corrects nothing
+            Input::Backspace => {
+                if self.remove_char() {
+                    self.key_strokes.push((at, KeyStrokeKind::Backspace(self.written_words.len() - 1)));
+                }
+            }
+            Input::DeleteWord => {
+                if self.remove_word() {
+                    self.key_strokes.push((at, KeyStrokeKind::Backspace(self.written_words.len() - 1)));
+                }
+            }
+        }
+    }
+
+    fn send_progress(&mut self, opponent: &Opponent) {
+        if self
+            .last_progress
+            .is_some_and(|sent| sent.elapsed() < PROGRESS_INTERVAL)
+        {
             return;
+        }
+        // the time spent paused doesn't count, like for the own wpm
+        let Some(elapsed) = self.elapsed() else {
+            return;
+        };
+        self.last_progress = Some(Instant::now());
+        let wpm = FinalStats::calculate(
+            &self.written_words[..self.written_words.len() - 1],
+            &self.word_list,
+            elapsed,
+            &self.mode,
+        )
+        .wpm;
+        opponent.send(&Message::Progress {
+            words: self.written_words.len() - 1,
+            wpm,
+        });
+    }
+
+    fn opponent_gauge(&self, opponent: &Opponent) -> Gauge<'static> {
+        let ratio = match self.mode {
+            TestMode::Words(words) => opponent.words as f64 / words as f64,
+            // without a word goal the bar is full while the opponent is ahead
+            TestMode::Duration(_) | TestMode::Chars(_) => {
+                opponent.words as f64 / opponent.words.max(self.written_words.len() - 1).max(1) as f64
+            }
+        };
+        let label = match (opponent.connected, opponent.finished) {
+            (_, true) => format!("opponent finished: {:.0} wpm", opponent.wpm),
+            (true, false) => format!("opponent: {} words, {:.0} wpm", opponent.words, opponent.wpm),
+            (false, false) => "opponent disconnected".to_string(),
+        };
+        Gauge::default()
+            .ratio(ratio.clamp(0.0, 1.0))
+            .gauge_style(Style::default().dark_gray())
+            .use_unicode(true)
+            .label(label)
+    }
+
+    /// Seconds, words or characters left, rounded up so it reaches 0 right as the test ends.
+    fn remaining(&self) -> u64 {
+        match self.mode {
+            TestMode::Duration(d) => {
+                let elapsed = self.elapsed().unwrap_or_default();
+                d.saturating_sub(elapsed).as_secs_f64().ceil() as u64
+            }
+            TestMode::Words(words) => words.saturating_sub(self.written_words.len() - 1) as u64,
+            TestMode::Chars(chars) => chars.saturating_sub(self.typed_chars()) as u64,
+        }
+    }
+
+    /// The accuracy of the last `GAUGE_ACCURACY_WINDOW` keystrokes, `None` before the first one.
+    fn recent_accuracy(&self) -> Option<f64> {
+        let window = &self.key_strokes[self.key_strokes.len().saturating_sub(GAUGE_ACCURACY_WINDOW)..];
+        let accuracy = calculate_accuracy(window);
+        // a window of only backspaces and spaces has nothing to be accurate about
+        (accuracy.correct + accuracy.incorrect > 0).then_some(accuracy.accuracy)
+    }
+
+    fn gauge_color(&self) -> Color {
+        match (self.accuracy_gauge, self.recent_accuracy()) {
+            (true, Some(accuracy)) => accuracy_color(accuracy),
+            _ => Color::Yellow,
+        }
+    }
+
+    /// The recent accuracy in percent, green when it reaches `accuracy_goal` and red below it.
+    fn accuracy_goal_indicator(&self) -> Option<Span<'static>> {
+        let goal = self.accuracy_goal?;
+        Some(match self.recent_accuracy() {
+            Some(accuracy) => {
+                let percent = accuracy * 100.0;
+                let color = match percent >= goal as f64 {
+                    true => Color::Green,
+                    false => Color::Red,
+                };
+                Span::styled(format!("{percent:.0}%"), Style::default().fg(color))
+            }
+            // the goal until there's an accuracy to compare to it
+            None => Span::styled(format!("{goal}%"), Style::default().dark_gray()),
+        })
+    }
+
+    /// Goes back from the start of a word to the end of the word before it,
+    /// unless that word is already correct and backspace isn't free.
+    /// Returns whether it went back.
+    fn back_to_previous_word(&mut self) -> bool {
+        let len = self.written_words.len();
+        let can_go_back = len > 1
+            && (self.free_backspace || !words_match(&self.word_list[len - 2], &self.written_words[len - 2]));
+        if can_go_back {
+            self.written_words.pop();
+        } else if len > 1 {
+            self.blocked_at = Some(Instant::now());
+        }
+        can_go_back
+    }
+
+    /// Whether anything was removed.
+    fn remove_char(&mut self) -> bool {
+        let Some(last) = self.written_words.last_mut() else {
+            return false;
         };
         if last.is_empty() {
-            self.remove_empty()
+            self.back_to_previous_word()
         } else {
-            last.pop();
+            last.pop().is_some()
         }
     }
-    fn remove_word(&mut self) {
+    /// Whether anything was removed.
+    fn remove_word(&mut self) -> bool {
         let Some(last) = self.written_words.last_mut() else {
-            return;
+            return false;
         };
         if last.is_empty() {
-            self.remove_empty();
-            self.written_words.last_mut().unwrap().clear();
+            // at the start of a word delete the whole previous word,
+            // or nothing if it can't be gone back to
+            let went_back = self.back_to_previous_word();
+            if went_back {
+                self.written_words.last_mut().unwrap().clear();
+            }
+            went_back
         } else {
             last.clear();
+            true
+        }
+    }
+
+    /// Whether the metronome is lit, for the first quarter of every beat from the start of the test.
+    fn metronome_beat(&self) -> bool {
+        match (self.metronome, self.elapsed()) {
+            (Some(beat), Some(elapsed)) if !beat.is_zero() => {
+                elapsed.as_nanos() % beat.as_nanos() < beat.as_nanos() / 4
+            }
+            _ => false,
         }
     }
 
-    fn add_char(&mut self, c: char, time: Instant) {
+    /// Whether the word boundary should flash because backspace was just blocked by it.
+    pub fn boundary_flashing(&self) -> bool {
+        self.boundary_flash && self.blocked_at.is_some_and(|at| at.elapsed() < BOUNDARY_FLASH)
+    }
+
+    /// A combining character, e.g. from a dead key, joins the letter before it,
+    /// so the key stroke of that letter is replaced instead of adding a new one.
+    fn add_char(&mut self, c: char, at: Duration) {
+        let i = self.written_words.len().saturating_sub(1);
         if let Some(s) = self.written_words.last_mut() {
+            let len_before = grapheme_len(s);
             s.push(c);
-            let len = s.len();
-            self.key_strokes.push((
-                time.elapsed(),
-                match self.word_list[self.written_words.len() - 1]
-                    .chars()
-                    .nth(len - 1)
-                    .is_some_and(|val| val == c)
-                {
-                    true => KeyStrokeKind::Correct(c),
-                    false => KeyStrokeKind::Incorrect(c),
-                },
-            ))
+            let len = grapheme_len(s);
+            let typed = s.graphemes(true).next_back().unwrap_or_default();
+            let base = typed.chars().next().unwrap_or(c);
+            let expected = self
+                .word_list
+                .get(i)
+                .and_then(|word| word.graphemes(true).nth(len - 1));
+            let kind = match expected {
+                Some(expected) if same_grapheme(expected, typed) => KeyStrokeKind::Correct(base),
+                Some(expected) if self.lenient_case && is_case_mismatch(expected, typed) => {
+                    KeyStrokeKind::WrongCase(base)
+                }
+                _ => KeyStrokeKind::Incorrect(base),
+            };
+            let replaces_letter = matches!(
+                self.key_strokes.last(),
+                Some((_, KeyStrokeKind::Correct(_) | KeyStrokeKind::Incorrect(_) | KeyStrokeKind::WrongCase(_)))
+            );
+            if len == len_before && replaces_letter {
+                self.key_strokes.pop();
+            }
+            self.word_started.get_or_insert(at);
+            self.key_strokes.push((at, kind))
+        }
+    }
+
+    /// Records how long the word being typed took, if any of it was typed.
+    fn close_word(&mut self, at: Duration) {
+        let i = self.written_words.len() - 1;
+        if let Some(started) = self.word_started.take() {
+            let word = self.word_list.get(i).unwrap_or(&self.written_words[i]).clone();
+            self.word_times.push((word, at.saturating_sub(started)));
         }
     }
 
-    fn add_space(&mut self, time: Instant) {
+    fn add_space(&mut self, at: Duration) {
         let i = self.written_words.len() - 1;
+        self.close_word(at);
         self.key_strokes.push((
-            time.elapsed(),
+            at,
             KeyStrokeKind::Space(
-                self.written_words[i].len() as i32 - self.word_list[i].len() as i32,
+                grapheme_len(&self.written_words[i]) as i32
+                    - self.word_list.get(i).map_or(0, |word| grapheme_len(word)) as i32,
             ),
         ));
         self.written_words.push(String::new());
+        self.fill_words();
     }
 }
 impl State for TypingState {
-    fn handle_event(mut self: Box<Self>, event: event::KeyEvent, _app: &App) -> Box<dyn State> {
-        if event.kind == KeyEventKind::Press {
+    fn handle_event(mut self: Box<Self>, event: Event, app: &App) -> Box<dyn State> {
+        let event = match event {
+            Event::FocusLost => {
+                log_event!(debug, "lost focus after {:?}", self.elapsed());
+                self.pause();
+                return self;
+            }
+            Event::FocusGained => {
+                log_event!(debug, "got focus back");
+                self.resume();
+                return self;
+            }
+            Event::Key(event) => event,
+            _ => return self,
+        };
+        // any key skips the rest of the hold
+        if let (KeyEventKind::Press, Some((_, test_duration))) = (event.kind, self.held) {
+            return (*self).finish(test_duration, app);
+        }
+        // keys pressed after the deadline, before `update` ends the test, are too late
+        if event.kind == KeyEventKind::Press && self.finished().is_none() {
+            // typing again also means the terminal has focus
+            self.resume();
+            if let (KeyCode::Enter, true, Some(start_time)) = (
+                event.code,
+                event.modifiers.contains(KeyModifiers::CONTROL),
+                self.start_time,
+            ) {
+                // the stats are normalized by the time typed, not the length of the test
+                let test_duration = self.key_stroke_time(start_time);
+                return (*self).finish(test_duration, app);
+            }
             // start counting the time on the first event
-            let time = *self.start_time.get_or_insert_with(|| Instant::now());
-            match event.code {
-                KeyCode::Char('w') | KeyCode::Backspace if event.modifiers.contains(KeyModifiers::CONTROL)  => {self.remove_word()},
-                KeyCode::Char(c @ ('!'..='~' /* https://www.asciitable.com/ */)) => self.add_char(c, time),
-                KeyCode::Char(' ') => self.add_space(time),
-                KeyCode::Backspace => self.remove_char(),
-                _ => (),
-            };
+            if self.start_time.is_none() {
+                log_event!(info, "test started");
+            }
+            let time = *self.start_time.get_or_insert_with(Instant::now);
+            if let Some(input) = key_input(&event) {
+                self.inputs.push((self.key_stroke_time(time), input));
+                self.apply(input, time);
+                if let (Input::Char(_), Some((_, key_stroke))) = (input, self.key_strokes.last()) {
+                    app.key_sound(key_stroke);
+                }
+            }
         }
         self
     }
-    fn update(self: Box<Self>, _app: &App) -> Box<dyn State> {
-        if let Some(start_time) = self.start_time {
-            match self.mode {
-                TestMode::Duration(dur) => {
-                    if start_time.elapsed() > dur {
-                        return Box::new(StatsState::new(
-                            self.key_strokes,
-                            dur,
-                            &self.word_list,
-                            &self.written_words,
-                            self.mode
-                        ));
-                    }
-                }
-                TestMode::Words(words) => {
-                    if self.written_words.len() > words {
-                        return Box::new(StatsState::new(
-                            self.key_strokes,
-                            start_time.elapsed(),
-                            &self.word_list,
-                            &self.written_words,
-                            self.mode
-                        ));
-                    }
-                }
-            }
+    fn update(mut self: Box<Self>, app: &App) -> Box<dyn State> {
+        if let Some(test_duration) = self.end_of_hold(Instant::now()) {
+            return (*self).finish(test_duration, app);
+        }
+        if let Some(opponent) = &app.opponent {
+            self.send_progress(opponent);
         }
         self
     }
-    fn render(&mut self, f: &mut ratatui::Frame<Backend>, _app: &App) {
+    fn animating(&self) -> bool {
+        // only the timer of a duration test moves on its own, and a hold has to end on time
+        matches!(self.mode, TestMode::Duration(_)) && self.start_time.is_some() && self.paused_at.is_none()
+            || self.held.is_some()
+            // redraw once the flash is over
+            || self.boundary_flashing()
+            || self.metronome.is_some() && self.start_time.is_some() && self.paused_at.is_none()
+    }
+    fn can_idle(&self) -> bool {
+        self.start_time.is_none()
+    }
+    fn render(&mut self, f: &mut ratatui::Frame<Backend>, app: &App) {
         let layout = Layout::default()
             .direction(Direction::Vertical)
-            .constraints(vec![Constraint::Max(1), Constraint::Min(0)])
+            .constraints(vec![
+                Constraint::Max(1),
+                Constraint::Max(1),
+                Constraint::Max(app.opponent.is_some().into()),
+                Constraint::Min(0),
+            ])
             .vertical_margin(1)
             .split(f.size());
 
+        let mut header = vec![
+            app.word_list.title(),
+            match self.mode {
+                TestMode::Duration(d) => format!("{}s", d.as_secs()),
+                TestMode::Words(words) => format!("{words} words"),
+                TestMode::Chars(chars) => format!("{chars} chars"),
+            },
+        ];
+        if let Some(day) = app.daily {
+            header.insert(0, format!("daily {}", history::format_day(day)));
+        }
+        if let Some(difficulty) = app.options.difficulty {
+            header.push(difficulty.to_string());
+        }
+        if app.options.punctuate {
+            header.push("punctuation".to_string());
+        }
+        if app.options.capitalize {
+            header.push("capitals".to_string());
+        }
+        let header = Paragraph::new(header.join(" | "))
+            .style(Style::default().dark_gray())
+            .alignment(Alignment::Center);
+
         let (ratio, label) = match self.mode {
             TestMode::Duration(d) => (
-                self.start_time
-                    .map_or(Duration::ZERO, |t| t.elapsed())
-                    .as_secs_f64()
-                    / d.as_secs_f64(),
-                self.start_time
-                    .map_or("Start Typing to begin.".to_string(), |duration| {
-                        format!(
-                            "{:.1}/{:.1}s",
-                            duration.elapsed().as_secs_f32(),
-                            d.as_secs_f32()
-                        )
+                self.elapsed().unwrap_or_default().as_secs_f64() / d.as_secs_f64(),
+                self.elapsed()
+                    .map_or("Start Typing to begin.".to_string(), |elapsed| {
+                        format!("{:.1}/{:.1}s", elapsed.as_secs_f32(), d.as_secs_f32())
                     }),
             ),
             TestMode::Words(words) => (
@@ -180,24 +727,469 @@ impl State for TypingState {
                     format!("{}/{}", (self.written_words.len() - 1), words)
                 },
             ),
+            TestMode::Chars(chars) => (
+                self.typed_chars() as f64 / chars as f64,
+                if self.start_time.is_none() {
+                    "Start Typing to begin.".to_string()
+                } else {
+                    format!("{}/{} chars", self.typed_chars(), chars)
+                },
+            ),
+        };
+        let label = match (self.held, self.paused_at) {
+            (Some(_), _) => "finished".to_string(),
+            (_, Some(_)) => "paused (unfocused)".to_string(),
+            _ => label,
         };
         let ratio = ratio.clamp(0.0, 1.0); // ratio thats not in 0..1.0 causes a panic
         let timer = Gauge::default()
             .ratio(ratio)
-            .gauge_style(Style::default().yellow())
+            .gauge_style(Style::default().fg(self.gauge_color()))
             .use_unicode(true)
             .label(label);
 
         let text_box_layout = Layout::new()
             .direction(Direction::Vertical)
             .constraints(vec![
-                Constraint::Percentage(30),
-                Constraint::Max(3),
-                Constraint::Percentage(40),
+                Constraint::Length(self.text_position.offset(layout[3].height)),
+                Constraint::Max(TEXT_BOX_HEIGHT),
+                Constraint::Min(0),
             ])
             .horizontal_margin(10)
-            .split(layout[1]);
-        f.render_widget(timer, layout[0]);
-        f.render_stateful_widget(TypingWidget::new(), text_box_layout[1], self);
+            .split(layout[3]);
+        f.render_widget(header, layout[0]);
+        let mut gauge_area = layout[1];
+        if self.metronome.is_some() {
+            let metronome_layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(0), Constraint::Length(METRONOME_WIDTH)])
+                .split(gauge_area);
+            gauge_area = metronome_layout[0];
+            let style = match self.metronome_beat() {
+                true => Style::default().yellow(),
+                false => Style::default().dark_gray(),
+            };
+            f.render_widget(Paragraph::new("●").style(style).alignment(Alignment::Right), metronome_layout[1]);
+        }
+        if let Some(indicator) = self.accuracy_goal_indicator() {
+            let goal_layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(0), Constraint::Length(ACCURACY_GOAL_WIDTH)])
+                .split(gauge_area);
+            gauge_area = goal_layout[0];
+            f.render_widget(Paragraph::new(indicator).alignment(Alignment::Right), goal_layout[1]);
+        }
+        // the row stays so the text doesn't move when the test starts
+        if !self.hide_progress || self.start_time.is_none() || self.paused_at.is_some() || self.held.is_some() {
+            match (self.sparkline, self.elapsed()) {
+                (true, Some(elapsed)) if gauge_area.width >= SPARKLINE_MIN_WIDTH => {
+                    let gauge_layout = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints([Constraint::Min(0), Constraint::Length(SPARKLINE_SECONDS as u16 + 1)])
+                        .split(gauge_area);
+                    let wpms = self.recent_wpms(elapsed);
+                    let sparkline = Sparkline::default()
+                        .data(&wpms)
+                        .style(Style::default().dark_gray());
+                    f.render_widget(timer, gauge_layout[0]);
+                    f.render_widget(sparkline, gauge_layout[1].inner(&Margin::new(1, 0)));
+                }
+                _ => f.render_widget(timer, gauge_area),
+            }
+        }
+        if let Some(opponent) = &app.opponent {
+            f.render_widget(self.opponent_gauge(opponent), layout[2]);
+        }
+        let widget = TypingWidget::new()
+            .active_word(self.active_word)
+            .dim_completed_lines(self.dim_completed_lines);
+        f.render_stateful_widget(widget, text_box_layout[1], self);
+        if self.big_timer {
+            let big_timer = Paragraph::new(bigtext::big_digits(&self.remaining().to_string()).join("\n"))
+                .style(Style::default().fg(self.gauge_color()))
+                .alignment(Alignment::Center);
+            let big_timer_layout = Layout::new()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Length(bigtext::HEIGHT as u16)])
+                .split(text_box_layout[2]);
+            f.render_widget(big_timer, big_timer_layout[1]);
+        }
+    }
+}
+
+/// The input a key press stands for. Shift and CapsLock already arrive as
+/// uppercase chars, so the typed char is compared as is.
+fn key_input(event: &KeyEvent) -> Option<Input> {
+    match event.code {
+        KeyCode::Char('w') | KeyCode::Backspace if event.modifiers.contains(KeyModifiers::CONTROL) => Some(Input::DeleteWord),
+        KeyCode::Char(' ') => Some(Input::Space),
+        KeyCode::Char(c) if is_typable(c) => Some(Input::Char(c)),
+        KeyCode::Backspace => Some(Input::Backspace),
+        _ => None,
+    }
+}
+
+/// Green at 100% accuracy, fading to red at 80% and below.
+fn accuracy_color(accuracy: f64) -> Color {
+    let t = ((accuracy - 0.8) / 0.2).clamp(0.0, 1.0);
+    let lerp = |from: f64, to: f64| (from + (to - from) * t).round() as u8;
+    Color::Rgb(lerp(220.0, 80.0), lerp(50.0, 200.0), lerp(50.0, 80.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn accuracy_colors() {
+        assert_eq!(accuracy_color(1.0), Color::Rgb(80, 200, 80));
+        assert_eq!(accuracy_color(0.5), Color::Rgb(220, 50, 50));
+        assert_eq!(accuracy_color(0.9), Color::Rgb(150, 125, 65));
+    }
+    #[test]
+    fn text_positions() {
+        for height in [0, 1, 3, 4, 20, 51] {
+            for position in [TextPosition::Upper, TextPosition::Center, TextPosition::Top] {
+                assert!(position.offset(height) + TEXT_BOX_HEIGHT.min(height) <= height);
+            }
+            let center = TextPosition::Center.offset(height);
+            assert!(height.saturating_sub(TEXT_BOX_HEIGHT) - 2 * center <= 1);
+        }
+        assert_eq!(TextPosition::Upper.offset(20), 6);
+        assert_eq!(TextPosition::Center.offset(20), 8);
+        assert_eq!(TextPosition::Top.offset(20), 0);
+    }
+    #[test]
+    fn apply_inputs() {
+        let words = ["ab", "cd"].map(String::from).to_vec();
+        let mut state = TypingState::new(words, TestMode::Words(2));
+        let start = Instant::now();
+        state.start(start);
+        for input in [Input::Char('a'), Input::Char('x'), Input::Backspace, Input::Char('b'), Input::Space] {
+            state.apply(input, start);
+        }
+        assert_eq!(state.written_words, ["ab", ""]);
+        assert_eq!(state.finished(), None);
+        for input in [Input::Char('c'), Input::Space, Input::DeleteWord, Input::Char('c'), Input::Char('d'), Input::Space] {
+            state.apply(input, start);
+        }
+        assert_eq!(state.written_words, ["ab", "cd", ""]);
+        assert!(state.finished().is_some());
+    }
+    #[test]
+    fn case_sensitivity() {
+        let shifted = KeyEvent::new(KeyCode::Char('H'), KeyModifiers::SHIFT);
+        assert_eq!(key_input(&shifted), Some(Input::Char('H')));
+        assert_eq!(key_input(&KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE)), Some(Input::Space));
+        assert_eq!(key_input(&KeyEvent::new(KeyCode::Char('\t'), KeyModifiers::NONE)), None);
+
+        let start = Instant::now();
+        for (lenient_case, expected) in [
+            (false, KeyStrokeKind::Incorrect('H')),
+            (true, KeyStrokeKind::WrongCase('H')),
+        ] {
+            let mut state = TypingState::new(vec!["hi".to_string()], TestMode::Words(1));
+            state.lenient_case = lenient_case;
+            state.start(start);
+            state.apply(Input::Char('H'), start);
+            state.apply(Input::Char('x'), start);
+            let kinds: Vec<_> = state.key_strokes.iter().map(|(_, ks)| ks.clone()).collect();
+            assert_eq!(kinds, [expected, KeyStrokeKind::Incorrect('x')]);
+        }
+    }
+    #[test]
+    fn accented_word() {
+        assert_eq!(key_input(&KeyEvent::new(KeyCode::Char('é'), KeyModifiers::NONE)), Some(Input::Char('é')));
+        let mut state = TypingState::new(vec!["café".to_string(), "жук".to_string()], TestMode::Words(2));
+        let start = Instant::now();
+        state.start(start);
+        for c in "café жук ".chars() {
+            state.apply(if c == ' ' { Input::Space } else { Input::Char(c) }, start);
+        }
+        assert_eq!(state.written_words, ["café", "жук", ""]);
+        assert_eq!(calculate_accuracy(&state.key_strokes).accuracy, 1.0);
+        assert!(state.key_strokes.iter().all(|(_, ks)| !matches!(ks, KeyStrokeKind::Space(n) if *n != 0)));
+    }
+    #[test]
+    fn dead_key_accent() {
+        let mut state = TypingState::new(vec!["caf\u{e9}".to_string()], TestMode::Words(1));
+        let start = Instant::now();
+        state.start(start);
+        for c in "cafe\u{301}".chars() {
+            state.apply(Input::Char(c), start);
+        }
+        state.apply(Input::Space, start);
+        let kinds: Vec<_> = state.key_strokes.iter().map(|(_, ks)| ks.clone()).collect();
+        assert_eq!(
+            kinds,
+            [
+                KeyStrokeKind::Correct('c'),
+                KeyStrokeKind::Correct('a'),
+                KeyStrokeKind::Correct('f'),
+                KeyStrokeKind::Correct('e'),
+                KeyStrokeKind::Space(0),
+            ]
+        );
+        assert!(state.finished().is_some());
+    }
+    #[test]
+    fn key_stroke_after_deadline() {
+        let mut state = TypingState::new(vec!["ab".to_string()], TestMode::Duration(Duration::from_secs(30)));
+        let start = Instant::now() - Duration::from_millis(30_050);
+        state.start(start);
+        state.apply(Input::Char('a'), start);
+        state.apply(Input::Space, start);
+        assert!(state.key_strokes.iter().all(|(time, _)| *time == Duration::from_secs(30)));
+        assert_eq!(state.finished(), Some(Duration::from_secs(30)));
+    }
+    #[test]
+    fn pause_stops_the_clock() {
+        let mut state = TypingState::new(vec!["ab".to_string()], TestMode::Duration(Duration::from_secs(30)));
+        let now = Instant::now();
+        state.start(now - Duration::from_secs(40));
+        state.paused_at = Some(now - Duration::from_secs(20));
+        assert_eq!(state.finished(), None);
+        assert_eq!(state.elapsed(), Some(Duration::from_secs(20)));
+        state.resume();
+        assert!(state.elapsed().unwrap() - Duration::from_secs(20) < Duration::from_secs(1));
+        state.pause();
+        assert!(state.paused_at.is_some());
+    }
+    /// Types `text` with `<` as backspace and `^` as Ctrl+W.
+    fn type_text(state: &mut TypingState, text: &str) {
+        for c in text.chars() {
+            let input = match c {
+                ' ' => Input::Space,
+                '<' => Input::Backspace,
+                '^' => Input::DeleteWord,
+                c => Input::Char(c),
+            };
+            state.apply_at(input, Duration::ZERO);
+        }
+    }
+    #[test]
+    fn delete_word_at_word_start() {
+        let words = ["ab", "cd", "ef"].map(String::from).to_vec();
+        // after a correct word nothing is deleted
+        let mut state = TypingState::new(words.clone(), TestMode::Words(3));
+        type_text(&mut state, "ab ^");
+        assert_eq!(state.written_words, ["ab", ""]);
+        // a word with a mistake is deleted whole
+        let mut state = TypingState::new(words.clone(), TestMode::Words(3));
+        type_text(&mut state, "ax ^");
+        assert_eq!(state.written_words, [""]);
+        type_text(&mut state, "ab cx ^");
+        assert_eq!(state.written_words, ["ab", ""]);
+        // one word at a time
+        let mut state = TypingState::new(words, TestMode::Words(3));
+        type_text(&mut state, "ax cx ^");
+        assert_eq!(state.written_words, ["ax", ""]);
+        type_text(&mut state, "^");
+        assert_eq!(state.written_words, [""]);
+    }
+    #[test]
+    fn backspace_across_words() {
+        let words = ["ab", "cd", "ef"].map(String::from).to_vec();
+        // a correct word can't be changed anymore
+        let mut state = TypingState::new(words.clone(), TestMode::Words(3));
+        type_text(&mut state, "ab <<");
+        assert_eq!(state.written_words, ["ab", ""]);
+        type_text(&mut state, "^");
+        assert_eq!(state.written_words, ["ab", ""]);
+        // but one with mistakes can
+        type_text(&mut state, "cx <");
+        assert_eq!(state.written_words, ["ab", "cx"]);
+        type_text(&mut state, "<d <");
+        assert_eq!(state.written_words, ["ab", "cd", ""]);
+        type_text(&mut state, "x ^^");
+        assert_eq!(state.written_words, ["ab", "cd", ""]);
+
+        let mut state = TypingState::new(words, TestMode::Words(3));
+        state.free_backspace = true;
+        type_text(&mut state, "ab cd <");
+        assert_eq!(state.written_words, ["ab", "cd"]);
+        type_text(&mut state, "^");
+        assert_eq!(state.written_words, ["ab", ""]);
+        type_text(&mut state, "^");
+        assert_eq!(state.written_words, [""]);
+        type_text(&mut state, "<");
+        assert_eq!(state.written_words, [""]);
+    }
+    #[test]
+    fn flash_blocked_backspace() {
+        let mut state = TypingState::new(["ab", "cd"].map(String::from).to_vec(), TestMode::Words(2));
+        // nothing to go back to at the very start
+        type_text(&mut state, "<");
+        assert!(!state.boundary_flashing());
+        type_text(&mut state, "ab <");
+        assert!(state.boundary_flashing());
+        assert!(state.animating());
+        state.blocked_at = None;
+        state.boundary_flash = false;
+        type_text(&mut state, "^");
+        assert!(!state.boundary_flashing());
+    }
+    #[test]
+    fn record_backspaces() {
+        let mut state = TypingState::new(["ab", "cd"].map(String::from).to_vec(), TestMode::Words(2));
+        type_text(&mut state, "ax<bb c<^");
+        let backspaces: Vec<_> = state
+            .key_strokes
+            .iter()
+            .filter_map(|(_, ks)| match ks {
+                KeyStrokeKind::Backspace(word) => Some(*word),
+                _ => None,
+            })
+            .collect();
+        // the Ctrl+W goes back to the mistyped first word
+        assert_eq!(backspaces, [0, 1, 0]);
+        assert_eq!(state.written_words, [""]);
+        // nothing to remove at the start of the test
+        let recorded = state.key_strokes.len();
+        type_text(&mut state, "<^");
+        assert_eq!(state.key_strokes.len(), recorded);
+    }
+    #[test]
+    fn blocked_backspaces_not_recorded() {
+        let mut state = TypingState::new(["ab", "cd"].map(String::from).to_vec(), TestMode::Words(2));
+        type_text(&mut state, "ab <^");
+        assert_eq!(state.written_words, ["ab", ""]);
+        assert!(!state.key_strokes.iter().any(|(_, ks)| matches!(ks, KeyStrokeKind::Backspace(_))));
+    }
+    #[test]
+    fn metronome_beats() {
+        let mut state = TypingState::new(["ab"].map(String::from).to_vec(), TestMode::Words(1));
+        state.metronome = Some(Duration::from_millis(500));
+        assert!(!state.metronome_beat());
+        for (elapsed, lit) in [(0, true), (100, true), (125, false), (499, false), (510, true), (1300, false)] {
+            state.start_time = Some(Instant::now() - Duration::from_millis(elapsed));
+            assert_eq!(state.metronome_beat(), lit, "{elapsed}ms");
+        }
+        assert!(state.animating());
+        state.metronome = None;
+        assert!(!state.metronome_beat());
+    }
+    #[test]
+    fn accuracy_goal_indicator() {
+        let mut state = TypingState::new(["abcd"].map(String::from).to_vec(), TestMode::Words(1));
+        assert_eq!(state.accuracy_goal_indicator(), None);
+        state.accuracy_goal = Some(90);
+        assert_eq!(state.accuracy_goal_indicator(), Some(Span::styled("90%", Style::default().dark_gray())));
+        type_text(&mut state, "abc");
+        assert_eq!(state.accuracy_goal_indicator(), Some(Span::styled("100%", Style::default().green())));
+        type_text(&mut state, "<x");
+        assert_eq!(state.accuracy_goal_indicator(), Some(Span::styled("75%", Style::default().red())));
+    }
+    #[test]
+    fn apply_recorded_inputs() {
+        let mut state = TypingState::new(vec!["ab".to_string(), "c".to_string()], TestMode::Words(2));
+        let secs = Duration::from_secs_f64;
+        for (at, input) in [(0.5, Input::Char('a')), (1.0, Input::Char('x')), (1.5, Input::Backspace), (2.0, Input::Char('b'))] {
+            state.apply_at(input, secs(at));
+        }
+        state.apply_at(Input::Space, secs(2.5));
+        assert_eq!(state.written_words, ["ab", ""]);
+        assert_eq!(
+            state.key_strokes().iter().map(|&(at, _)| at).collect::<Vec<_>>(),
+            [secs(0.5), secs(1.0), secs(1.5), secs(2.0), secs(2.5)]
+        );
+        assert_eq!(state.word_times, [("ab".to_string(), secs(2.0))]);
+    }
+    #[test]
+    fn word_timing() {
+        let mut state = TypingState::new(["one", "two", "three"].map(String::from).to_vec(), TestMode::Words(3));
+        let secs = Duration::from_secs_f64;
+        state.word_started = Some(secs(0.5));
+        state.close_word(secs(1.25));
+        state.written_words.push(String::new());
+        // a space without any letters isn't a word
+        state.close_word(secs(2.0));
+        assert_eq!(state.word_times, [("one".to_string(), secs(0.75))]);
+
+        let start = Instant::now();
+        state.start(start);
+        for input in [Input::Char('t'), Input::Backspace, Input::Char('t'), Input::Char('w'), Input::Space] {
+            state.apply(input, start);
+        }
+        assert_eq!(state.word_times.len(), 2);
+        assert_eq!(state.word_times[1].0, "two");
+        assert_eq!(state.word_started, None);
+    }
+    #[test]
+    fn chars_test_ends_at_limit() {
+        let mut state = TypingState::new(["abc", "def", "ghi"].map(String::from).to_vec(), TestMode::Chars(6));
+        let start = Instant::now() - Duration::from_secs(12);
+        state.start(start);
+        for c in "abc d".chars() {
+            state.apply(if c == ' ' { Input::Space } else { Input::Char(c) }, start);
+        }
+        assert_eq!(state.finished(), None);
+        assert_eq!(state.remaining(), 1);
+        state.apply(Input::Char('e'), start);
+        let test_duration = state.finished().unwrap();
+        assert!(test_duration >= Duration::from_secs(12));
+        // the stats are over the time it took, with the half typed last word
+        let stats = state.into_stats(Duration::from_secs(12), 10);
+        assert_eq!(stats.wpm(), 6.0);
+
+        // a shorter text ends with its last word
+        let mut state = TypingState::new(vec!["ab".to_string()], TestMode::Chars(100));
+        state.start(start);
+        for input in [Input::Char('a'), Input::Char('b'), Input::Space] {
+            state.apply(input, start);
+        }
+        assert!(state.finished().is_some());
+    }
+    #[test]
+    fn hold_finished_text() {
+        let mut state = TypingState::new(vec!["a".to_string()], TestMode::Words(1));
+        let start = Instant::now();
+        state.start(start);
+        state.finish_hold = Duration::from_secs(1);
+        assert_eq!(state.end_of_hold(start), None);
+        assert_eq!(state.held, None);
+        state.written_words = vec!["a".to_string(), String::new()];
+        let finished = start + Duration::from_secs(5);
+        assert_eq!(state.end_of_hold(finished), None);
+        let (_, test_duration) = state.held.unwrap();
+        // the words test lasts until it was finished, not until the end of the hold
+        assert_eq!(
+            state.end_of_hold(finished + Duration::from_secs(1)),
+            Some(test_duration)
+        );
+
+        let mut state = TypingState::new(vec!["a".to_string()], TestMode::Duration(Duration::from_secs(1)));
+        state.start(start - Duration::from_secs(2));
+        assert_eq!(state.end_of_hold(start), Some(Duration::from_secs(1)));
+    }
+    #[test]
+    fn sparkline_wpms() {
+        let mut state = TypingState::new(vec!["abc".to_string(); 10], TestMode::Words(10));
+        let at = |secs: f64| (Duration::from_secs_f64(secs), KeyStrokeKind::Correct('a'));
+        state.key_strokes = vec![at(0.2), at(0.5), at(1.5)];
+        // the second that is still going isn't shown
+        assert_eq!(state.recent_wpms(Duration::from_secs_f64(1.9)), [24]);
+        state.key_strokes.extend([at(1.8), at(3.5)]);
+        assert_eq!(state.recent_wpms(Duration::from_secs(4)), [24, 24, 0, 12]);
+        assert_eq!(state.binned_key_strokes, 5);
+        assert_eq!(state.recent_wpms(Duration::from_secs(100)).len(), SPARKLINE_SECONDS);
+    }
+    #[test]
+    fn duration_test_never_runs_out_of_words() {
+        let words = ["a", "b", "c"].map(String::from).to_vec();
+        let mut state = TypingState::new(words.clone(), TestMode::Duration(Duration::from_secs(60)));
+        state.refill_from(WordPool::new(words.clone(), SmallRng::seed_from_u64(0), true));
+        let start = Instant::now();
+        state.start(start);
+        for _ in 0..500 {
+            let word = state.word_list[state.written_words.len() - 1].clone();
+            for c in word.chars() {
+                state.apply(Input::Char(c), start);
+            }
+            state.apply(Input::Space, start);
+        }
+        assert_eq!(state.written_words.len(), 501);
+        assert!(state.word_list.len() >= 501 + WORDS_AHEAD);
+        assert!(state.word_list.iter().all(|word| words.contains(word)));
+        assert!(calculate_accuracy(&state.key_strokes).accuracy == 1.0);
     }
 }
@@ -0,0 +1,39 @@
+//! Helpers shared by the tests.
+
+use std::{
+    env, fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+/// A path in the temporary directory that's removed when it's dropped, as a
+/// file or a directory, so a failing test doesn't leave anything behind.
+pub struct TempPath(PathBuf);
+
+impl TempPath {
+    /// `name` has to be unique among the tests, the process id keeps test runs apart.
+    pub fn new(name: &str) -> Self {
+        Self(env::temp_dir().join(format!("crabtype-{}-{name}", std::process::id())))
+    }
+}
+
+impl Deref for TempPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempPath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        // the test may have removed it already
+        let _ = fs::remove_file(&self.0).or_else(|_| fs::remove_dir_all(&self.0));
+    }
+}
@@ -1,23 +1,57 @@
-use std::{iter, borrow::Cow};
+use std::{iter, mem};
 
-use ratatui::{prelude::*, widgets::StatefulWidget};
+use ratatui::{buffer::Cell, prelude::*, widgets::StatefulWidget};
 
-use crate::states::{self, word_difference, CharDiffKind};
+use crabtype::analysis::{grapheme_len, grapheme_prefix, word_difference, CharDiffKind};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::states;
 
 pub struct TypingWidget {
     style_correct: Style,
     style_error: Style,
+    /// letters in the wrong case when case mismatches are lenient
+    style_wrong_case: Style,
     style_untyped: Style,
+    style_extra: Style,
+    style_missed: Style,
+    /// symbols in the untyped text when punctuation hints are on
+    style_punctuation: Style,
     style_cursor: Style,
+    /// the gap before the word being typed when backspace can't go past it
+    style_blocked: Style,
+    /// patched on top of the per character styles of the word being typed
+    style_active_word: Style,
+    /// dims the lines above the one with the word being typed
+    dim_completed_lines: bool,
 }
 impl TypingWidget {
     pub fn new() -> Self {
         Self {
             style_correct: Style::default().green(),
             style_error: Style::default().red(),
+            style_wrong_case: Style::default().yellow(),
             style_untyped: Style::default().dark_gray(),
+            style_extra: Style::default().red().dim(),
+            style_missed: Style::default().dark_gray().underlined(),
+            style_punctuation: Style::default().gray(),
             style_cursor: Style::default().on_white(),
+            style_blocked: Style::default().on_dark_gray(),
+            style_active_word: Style::default().bold(),
+            dim_completed_lines: true,
+        }
+    }
+    /// Whether the word being typed gets `style_active_word`, with `false` it only has the styles of its letters.
+    pub fn active_word(mut self, highlight: bool) -> Self {
+        if !highlight {
+            self.style_active_word = Style::default();
         }
+        self
+    }
+    pub fn dim_completed_lines(mut self, dim: bool) -> Self {
+        self.dim_completed_lines = dim;
+        self
     }
     fn render_input_dif(
         &self,
@@ -25,48 +59,79 @@ impl TypingWidget {
         word: &String,
         buf: &mut Buffer,
         area: Rect,
-        x: u16,
-        y: u16,
+        is_current: bool,
+        lenient_case: bool,
     ) {
         if input == word {
-            buf.set_style(
-                Rect {
-                    x: area.x + x,
-                    y: area.y + y,
-                    width: word.len() as u16,
-                    height: 1,
-                },
-                self.style_correct,
-            )
+            buf.set_style(area, self.style_correct)
         } else {
-            for (i, dif) in word_difference(word, input).enumerate() {
+            let mut offset = 0;
+            for (dif, (expected, typed)) in word_difference(word, input).zip(
+                word.graphemes(true).map(Some).chain(iter::repeat(None))
+                    .zip(input.graphemes(true).map(Some).chain(iter::repeat(None))),
+            ) {
+                if offset >= area.width {
+                    break;
+                }
+                // the typed letter is shown where there is one, otherwise the one of the word
+                let width = typed.or(expected).map_or(0, |g| g.width() as u16);
                 buf.set_style(
                     Rect {
-                        x: area.x + x + i as u16,
-                        y: area.y + y,
-                        width: 1,
+                        x: area.x + offset,
+                        y: area.y,
+                        width: width.min(area.width - offset),
                         height: 1,
                     },
                     match dif {
                         CharDiffKind::Correct => self.style_correct,
-                        CharDiffKind::Incorrect => self.style_error,
-                        CharDiffKind::Extra => self.style_error.underlined(),
-                        CharDiffKind::Missed => Style::default().underlined()
+                        CharDiffKind::CaseError if lenient_case => self.style_wrong_case,
+                        CharDiffKind::Incorrect | CharDiffKind::CaseError => self.style_error,
+                        CharDiffKind::Extra => self.style_extra,
+                        // the rest of the word being typed isn't missed yet
+                        CharDiffKind::Missed if is_current => self.style_untyped,
+                        CharDiffKind::Missed => self.style_missed,
                     }
-                )
+                );
+                offset += width;
             }
         }
     }
-    fn combine_input<'a>(input: Option<&'a String>, word: &'a String) -> Cow<'a, str> {
-        match input {
-            None => word.into(),
-            Some(s) => {
-                if word.len() > s.len() {
-                    (s.clone() + &word[s.len()..]).into()
-                } else {
-                    s.into()
-                }
+    /// Flips the rows of `area` so the text reads from right to left. The layout
+    /// is done left to right as usual, which keeps the wrapping, indentation and
+    /// cursor arithmetic the same, and the cells of a wide letter stay together.
+    fn mirror(buf: &mut Buffer, area: Rect) {
+        for y in area.top()..area.bottom() {
+            let cells: Vec<Cell> = (area.left()..area.right()).map(|x| buf.get(x, y).clone()).collect();
+            let mut letters = Vec::new();
+            let mut i = 0;
+            while i < cells.len() {
+                let width = cells[i].symbol.width().clamp(1, cells.len() - i);
+                letters.push(&cells[i..i + width]);
+                i += width;
+            }
+            for (x, cell) in (area.left()..).zip(letters.into_iter().rev().flatten()) {
+                *buf.get_mut(x, y) = cell.clone();
+            }
+        }
+    }
+    /// Styles the symbols of the untyped `rest` of a word drawn from `x`, up to `right`.
+    fn render_punctuation_hints(&self, rest: &str, buf: &mut Buffer, mut x: u16, y: u16, right: u16) {
+        for grapheme in rest.graphemes(true) {
+            let width = grapheme.width() as u16;
+            if x + width > right {
+                break;
             }
+            if !grapheme.chars().any(char::is_alphanumeric) {
+                buf.set_style(Rect::new(x, y, width, 1), self.style_punctuation);
+            }
+            x += width;
+        }
+    }
+    /// The part of `word` that is shown after the typed input, without allocating.
+    fn untyped_rest<'a>(input: Option<&String>, word: &'a str) -> &'a str {
+        match input {
+            None => word,
+            Some(input) => &word[grapheme_prefix(word, grapheme_len(input)).len()..],
         }
     }
 }
@@ -76,7 +141,11 @@ impl StatefulWidget for TypingWidget {
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         let (mut x, mut y) = (0, 0);
 
-        let mut new_rows = vec![state.rows[0]];
+        // reuse the allocation of the rows from the last frame
+        let first_row = state.rows[0];
+        let mut new_rows = mem::take(&mut state.rows);
+        new_rows.truncate(1);
+        let mut current_y = None;
 
         for (word, (input_index, input)) in state
             .word_list
@@ -89,26 +158,69 @@ impl StatefulWidget for TypingWidget {
                     .chain(iter::repeat(None))
                     .enumerate(),
             )
-            .skip(state.rows[0])
+            .skip(first_row)
         {
-            let word_to_display = Self::combine_input(input, word);
-            if x + word_to_display.len() as u16 > area.width {
-                y += 1;
+            let typed = input.map_or("", |input| input.as_str());
+            let rest = Self::untyped_rest(input, word);
+            let display_len = (typed.width() + rest.width()) as u16;
+            // every break before the last one at the same word is a blank line
+            let breaks = state.line_breaks.partition_point(|line_break| line_break.word < input_index)
+                ..state.line_breaks.partition_point(|line_break| line_break.word <= input_index);
+            let blank_lines = breaks.len().saturating_sub(1) as u16;
+            let line_break = state.line_breaks[breaks].last().copied();
+            // a word wider than the whole area is cut off instead of wrapped again
+            if x > 0 && (x + display_len > area.width || line_break.is_some()) {
+                y += 1 + blank_lines;
                 x = 0;
                 new_rows.push(input_index);
             }
+            // hidden words still take up their space, so the text doesn't move when they show up
+            let hidden = state
+                .reveal
+                .is_some_and(|ahead| input_index > (state.written_words.len() - 1).saturating_add(ahead));
+            if let (true, false, Some(glyph)) = (x > 0, hidden, state.separator.glyph) {
+                // in the middle of the gap before this word
+                let glyph_x = x - state.separator.width + (state.separator.width - 1) / 2;
+                buf.get_mut(area.x + glyph_x, area.y + y)
+                    .set_char(glyph)
+                    .set_style(self.style_untyped);
+            }
+            if x > 0 && y < area.height && input_index == state.written_words.len() - 1 && state.boundary_flashing() {
+                buf.set_style(
+                    Rect {
+                        x: area.x + x - state.separator.width,
+                        y: area.y + y,
+                        width: state.separator.width,
+                        height: 1,
+                    },
+                    self.style_blocked,
+                );
+            }
+            if let (0, Some(line_break)) = (x, line_break) {
+                // the indentation is only shown, like the auto indent of an editor
+                x = (line_break.indent as u16).min(area.width.saturating_sub(1));
+            }
             if y >= area.height {
                 break;
             }
+            if hidden {
+                x += display_len + state.separator.width;
+                continue;
+            }
             if input_index == state.written_words.len() - 1 {
                 if y >= 2 {
                     new_rows.remove(0);
                 }
-                let mut cursor_x = x + input.unwrap().len() as u16;
+                let mut cursor_x = x + input.unwrap().width() as u16;
                 let mut cursor_y = y;
                 if cursor_x >= area.width {
-                    cursor_x = 0;
-                    cursor_y += 1;
+                    // keep the cursor inside the area when there's no next line or the word is cut off
+                    if x + display_len <= area.width && cursor_y + 1 < area.height {
+                        cursor_x = 0;
+                        cursor_y += 1;
+                    } else {
+                        cursor_x = area.width - 1;
+                    }
                 }
                 buf.set_style(
                     Rect {
@@ -120,12 +232,357 @@ impl StatefulWidget for TypingWidget {
                     self.style_cursor,
                 )
             }
-            buf.set_string(x + area.x, y + area.y, &word_to_display, self.style_untyped);
+            let word_area = Rect {
+                x: area.x + x,
+                y: area.y + y,
+                width: display_len.min(area.width.saturating_sub(x)),
+                height: 1,
+            };
+            let (rest_x, _) = buf.set_stringn(
+                word_area.x,
+                word_area.y,
+                typed,
+                word_area.width as usize,
+                self.style_untyped,
+            );
+            if rest_x < word_area.right() {
+                buf.set_stringn(
+                    rest_x,
+                    word_area.y,
+                    rest,
+                    (word_area.right() - rest_x) as usize,
+                    self.style_untyped,
+                );
+            }
             if let Some(input) = input {
-                self.render_input_dif(input, word, buf, area, x, y);
+                let is_current = input_index == state.written_words.len() - 1;
+                self.render_input_dif(input, word, buf, word_area, is_current, state.lenient_case);
+                if is_current {
+                    buf.set_style(word_area, self.style_active_word);
+                    current_y = Some(y);
+                }
             }
-            x += word_to_display.len() as u16 + 1;
+            // the rest of a typed word is missed, not a hint anymore
+            if state.punctuation_hints && input_index >= state.written_words.len() - 1 {
+                self.render_punctuation_hints(rest, buf, rest_x, word_area.y, word_area.right());
+            }
+            x += display_len + state.separator.width;
+        }
+        if let Some(current_y) = current_y.filter(|_| self.dim_completed_lines) {
+            buf.set_style(
+                Rect {
+                    height: current_y,
+                    ..area
+                },
+                Style::default().dim(),
+            );
+        }
+        if state.rtl {
+            Self::mirror(buf, area);
         }
         state.rows = new_rows;
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crabtype::{
+        analysis::TestMode,
+        replay::Input,
+        words::LineBreak,
+    };
+
+    use super::*;
+    use crate::states::TypingState;
+
+    fn render(state: &mut TypingState, width: u16, height: u16) -> Buffer {
+        render_with(TypingWidget::new(), state, width, height)
+    }
+
+    fn render_with(widget: TypingWidget, state: &mut TypingState, width: u16, height: u16) -> Buffer {
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf, state);
+        buf
+    }
+
+    fn assert_style(buf: &Buffer, x: u16, y: u16, style: Style) {
+        let cell = buf.get(x, y);
+        assert_eq!(Some(cell.fg), style.fg, "fg at ({x}, {y})");
+        assert_eq!(cell.modifier, style.add_modifier, "modifier at ({x}, {y})");
+    }
+
+    fn line(buf: &Buffer, y: u16) -> String {
+        (0..buf.area.width)
+            .map(|x| buf.get(x, y).symbol.as_str())
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    fn typing_state(words: &[&str], written: &[&str]) -> TypingState {
+        let mut state = TypingState::new(
+            words.iter().map(|w| w.to_string()).collect(),
+            TestMode::Duration(Duration::from_secs(30)),
+        );
+        state.written_words = written.iter().map(|w| w.to_string()).collect();
+        state
+    }
+
+    #[test]
+    fn missed_and_extra_styles() {
+        let widget = TypingWidget::new();
+        let mut state = typing_state(&["abc", "de"], &["ab", "dex"]);
+        let buf = render(&mut state, 20, 3);
+        assert_style(&buf, 2, 0, widget.style_missed);
+        assert_style(&buf, 6, 0, widget.style_extra.patch(widget.style_active_word));
+
+        // the rest of the current word isn't marked as missed
+        let mut state = typing_state(&["abc", "def"], &["ab", "d"]);
+        let buf = render(&mut state, 20, 3);
+        assert_style(&buf, 6, 0, widget.style_untyped.patch(widget.style_active_word));
+    }
+    #[test]
+    fn empty_input_snapshot() {
+        let widget = TypingWidget::new();
+        let mut state = typing_state(&["ab", "cd", "efg"], &[""]);
+        let buf = render(&mut state, 6, 2);
+        let mut expected = Buffer::with_lines(vec!["ab cd ", "efg   "]);
+        expected.set_style(Rect::new(0, 0, 2, 1), widget.style_untyped.patch(widget.style_active_word));
+        expected.set_style(Rect::new(3, 0, 2, 1), widget.style_untyped);
+        expected.set_style(Rect::new(0, 1, 3, 1), widget.style_untyped);
+        expected.set_style(Rect::new(0, 0, 1, 1), widget.style_cursor);
+        assert_eq!(buf, expected);
+    }
+    #[test]
+    fn partial_word_snapshot() {
+        let widget = TypingWidget::new();
+        let mut state = typing_state(&["ab", "cde", "f"], &["ab", "cx"]);
+        let buf = render(&mut state, 8, 1);
+        let mut expected = Buffer::with_lines(vec!["ab cxe f"]);
+        expected.set_style(Rect::new(0, 0, 2, 1), widget.style_correct);
+        let active = |style: Style| style.patch(widget.style_active_word);
+        expected.set_style(Rect::new(3, 0, 1, 1), active(widget.style_correct));
+        expected.set_style(Rect::new(4, 0, 1, 1), active(widget.style_error));
+        expected.set_style(Rect::new(5, 0, 1, 1), active(widget.style_untyped));
+        expected.set_style(Rect::new(5, 0, 1, 1), widget.style_cursor);
+        expected.set_style(Rect::new(7, 0, 1, 1), widget.style_untyped);
+        assert_eq!(buf, expected);
+    }
+    #[test]
+    fn over_typed_word_snapshot() {
+        let widget = TypingWidget::new();
+        // the extra letters take up room, so the last word wraps
+        let mut state = typing_state(&["ab", "cd", "ef"], &["abxx", "c"]);
+        let buf = render(&mut state, 7, 2);
+        let mut expected = Buffer::with_lines(vec!["abxx cd", "ef     "]);
+        expected.set_style(Rect::new(0, 0, 2, 1), widget.style_correct);
+        expected.set_style(Rect::new(2, 0, 2, 1), widget.style_extra);
+        let active = |style: Style| style.patch(widget.style_active_word);
+        expected.set_style(Rect::new(5, 0, 1, 1), active(widget.style_correct));
+        expected.set_style(Rect::new(6, 0, 1, 1), active(widget.style_untyped));
+        expected.set_style(Rect::new(6, 0, 1, 1), widget.style_cursor);
+        expected.set_style(Rect::new(0, 1, 2, 1), widget.style_untyped);
+        assert_eq!(buf, expected);
+    }
+    #[test]
+    fn blocked_backspace_flashes_gap() {
+        let mut state = typing_state(&["ab", "cd"], &["ab", ""]);
+        let buf = render(&mut state, 20, 3);
+        assert_eq!(buf.get(2, 0).bg, Color::Reset);
+        state.apply_at(Input::Backspace, Duration::ZERO);
+        let buf = render(&mut state, 20, 3);
+        assert_eq!(buf.get(2, 0).bg, Color::DarkGray);
+        assert_eq!(buf.get(1, 0).bg, Color::Reset);
+    }
+    #[test]
+    fn overflow_wraps_next_word() {
+        let mut state = typing_state(&["abc", "def", "gh", "ij"], &["abc", "de"]);
+        let buf = render(&mut state, 8, 3);
+        assert_eq!(line(&buf, 0), "abc def");
+        assert_eq!(line(&buf, 1), "gh ij");
+
+        // over typing the last word of the line pushes it and everything after it down
+        let mut state = typing_state(&["abc", "def", "gh", "ij"], &["abc", "defxx", "g"]);
+        let buf = render(&mut state, 8, 3);
+        assert_eq!(line(&buf, 0), "abc");
+        assert_eq!(line(&buf, 1), "defxx gh");
+        assert_eq!(line(&buf, 2), "ij");
+    }
+    #[test]
+    fn cursor_stays_in_area() {
+        let mut state = typing_state(&["abcd", "ef"], &["abcd"]);
+        let buf = render(&mut state, 4, 1);
+        assert_eq!(line(&buf, 0), "abcd");
+        assert_eq!(buf.get(3, 0).bg, TypingWidget::new().style_cursor.bg.unwrap());
+    }
+    #[test]
+    fn word_wider_than_area() {
+        let url = "https://example.com/a/very/long/path";
+        let mut state = typing_state(&["ab", url, "cd"], &["ab", "https"]);
+        let buf = render(&mut state, 10, 3);
+        assert_eq!(line(&buf, 0), "ab");
+        assert_eq!(line(&buf, 1), &url[..10]);
+        assert_eq!(line(&buf, 2), "cd");
+
+        // nothing is drawn past the edge of the area
+        let mut state = typing_state(&[url, "cd"], &[url]);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 2));
+        TypingWidget::new().render(Rect::new(0, 0, 10, 2), &mut buf, &mut state);
+        assert_eq!(line(&buf, 0), &url[..10]);
+        assert_eq!(line(&buf, 1), "cd");
+    }
+    #[test]
+    fn active_word_style() {
+        let widget = TypingWidget::new();
+        let mut state = typing_state(&["abc", "def", "gh"], &["abc", "dx"]);
+        let buf = render(&mut state, 20, 3);
+        assert_style(&buf, 0, 0, widget.style_correct);
+        assert_style(&buf, 4, 0, widget.style_correct.patch(widget.style_active_word));
+        assert_style(&buf, 5, 0, widget.style_error.patch(widget.style_active_word));
+        assert_style(&buf, 8, 0, widget.style_untyped);
+    }
+    #[test]
+    fn wrong_case_style() {
+        let widget = TypingWidget::new();
+        let mut state = typing_state(&["abc", "de"], &["aBx", "d"]);
+        let buf = render(&mut state, 20, 3);
+        assert_style(&buf, 1, 0, widget.style_error);
+
+        state.lenient_case = true;
+        let buf = render(&mut state, 20, 3);
+        assert_style(&buf, 1, 0, widget.style_wrong_case);
+        assert_style(&buf, 2, 0, widget.style_error);
+    }
+    #[test]
+    fn non_ascii_words() {
+        let widget = TypingWidget::new();
+        let mut state = typing_state(&["xé", "ñu"], &["é", "ñ"]);
+        let buf = render(&mut state, 20, 3);
+        assert_eq!(line(&buf, 0), "éé ñu");
+        assert_style(&buf, 0, 0, widget.style_error);
+        assert_style(&buf, 3, 0, widget.style_correct.patch(widget.style_active_word));
+        assert_eq!(buf.get(4, 0).bg, widget.style_cursor.bg.unwrap());
+    }
+    #[test]
+    fn wide_graphemes() {
+        let widget = TypingWidget::new();
+        let mut state = typing_state(&["🦀a", "b"], &["🦀x"]);
+        let buf = render(&mut state, 20, 3);
+        assert_eq!(line(&buf, 0).replace(' ', ""), "🦀xb");
+        assert_style(&buf, 0, 0, widget.style_correct.patch(widget.style_active_word));
+        assert_style(&buf, 1, 0, widget.style_correct.patch(widget.style_active_word));
+        assert_style(&buf, 2, 0, widget.style_error.patch(widget.style_active_word));
+        assert_eq!(buf.get(3, 0).bg, widget.style_cursor.bg.unwrap());
+        assert_eq!(buf.get(4, 0).symbol, "b");
+    }
+    #[test]
+    fn punctuation_hints() {
+        let widget = TypingWidget::new();
+        let mut state = typing_state(&["ab,", "(cd)", "e.f"], &["ab,", "("]);
+        let buf = render(&mut state, 20, 3);
+        assert_style(&buf, 10, 0, widget.style_untyped);
+
+        state.punctuation_hints = true;
+        let buf = render(&mut state, 20, 3);
+        // typed symbols keep the style of what was typed
+        assert_style(&buf, 2, 0, widget.style_correct);
+        assert_style(&buf, 4, 0, widget.style_correct.patch(widget.style_active_word));
+        assert_style(&buf, 6, 0, widget.style_untyped.patch(widget.style_active_word));
+        assert_style(&buf, 7, 0, widget.style_punctuation.patch(widget.style_active_word));
+        assert_style(&buf, 9, 0, widget.style_untyped);
+        assert_style(&buf, 10, 0, widget.style_punctuation);
+        assert_style(&buf, 11, 0, widget.style_untyped);
+    }
+    #[test]
+    fn reveal_words_ahead() {
+        let mut state = typing_state(&["ab", "cd", "ef", "gh"], &["ab", "c"]);
+        state.separator.glyph = Some('·');
+        state.reveal = Some(1);
+        let buf = render(&mut state, 20, 3);
+        assert_eq!(line(&buf, 0), "ab·cd·ef");
+        state.reveal = Some(0);
+        let buf = render(&mut state, 20, 3);
+        assert_eq!(line(&buf, 0), "ab·cd");
+        // the words keep their place once they are shown
+        state.written_words = ["ab", "cd", "ef", ""].map(String::from).to_vec();
+        let buf = render(&mut state, 20, 3);
+        assert_eq!(line(&buf, 0), "ab·cd·ef·gh");
+        // everything is shown without overflowing
+        state.reveal = Some(usize::MAX);
+        let buf = render(&mut state, 20, 3);
+        assert_eq!(line(&buf, 0), "ab·cd·ef·gh");
+    }
+    #[test]
+    fn line_breaks() {
+        let mut state = typing_state(&["roses", "are", "red", "violets"], &["ro"]);
+        state.line_breaks = vec![LineBreak { word: 3, indent: 2 }];
+        let buf = render(&mut state, 40, 3);
+        assert_eq!(line(&buf, 0), "roses are red");
+        assert_eq!(line(&buf, 1), "  violets");
+
+        state.line_breaks.insert(0, LineBreak { word: 3, indent: 0 });
+        let buf = render(&mut state, 40, 3);
+        assert_eq!(line(&buf, 1), "");
+        assert_eq!(line(&buf, 2), "  violets");
+    }
+    #[test]
+    fn separators() {
+        let mut state = typing_state(&["ab", "cd", "ef"], &[""]);
+        state.separator = states::Separator {
+            width: 3,
+            glyph: Some('·'),
+        };
+        let buf = render(&mut state, 9, 3);
+        assert_eq!(line(&buf, 0), "ab · cd");
+        // no separator at the start of a line
+        assert_eq!(line(&buf, 1), "ef");
+        assert_style(&buf, 3, 0, TypingWidget::new().style_untyped);
+
+        state.separator.glyph = None;
+        let buf = render(&mut state, 20, 3);
+        assert_eq!(line(&buf, 0), "ab   cd   ef");
+    }
+    #[test]
+    fn right_to_left() {
+        let widget = TypingWidget::new();
+        let mut state = typing_state(&["שלום", "עולם"], &["של"]);
+        state.rtl = true;
+        let buf = render(&mut state, 12, 3);
+        assert_eq!(line(&buf, 0), "   םלוע םולש");
+        assert_style(&buf, 11, 0, widget.style_correct.patch(widget.style_active_word));
+        assert_style(&buf, 10, 0, widget.style_correct.patch(widget.style_active_word));
+        // the cursor is on the next letter to type, to the left of the typed ones
+        assert_eq!(buf.get(9, 0).symbol, "ו");
+        assert_eq!(buf.get(9, 0).bg, widget.style_cursor.bg.unwrap());
+
+        let mut state = typing_state(&["🦀a"], &[""]);
+        state.rtl = true;
+        let buf = render(&mut state, 5, 1);
+        assert_eq!(line(&buf, 0), "  a🦀");
+    }
+    #[test]
+    fn active_word_disabled() {
+        let widget = TypingWidget::new();
+        let mut state = typing_state(&["abc", "def"], &["abc", "dx"]);
+        let buf = render_with(TypingWidget::new().active_word(false), &mut state, 20, 1);
+        assert_style(&buf, 4, 0, widget.style_correct);
+        assert_style(&buf, 5, 0, widget.style_error);
+        assert_style(&buf, 6, 0, widget.style_untyped);
+    }
+    #[test]
+    fn completed_lines_dimmed() {
+        let widget = TypingWidget::new();
+        let mut state = typing_state(&["abc", "def", "gh"], &["abc", "def", "g"]);
+        let buf = render(&mut state, 8, 3);
+        assert_style(&buf, 0, 0, widget.style_correct.dim());
+        assert_style(&buf, 4, 0, widget.style_correct.dim());
+        assert_style(&buf, 0, 1, widget.style_correct.patch(widget.style_active_word));
+
+        let buf = render_with(TypingWidget::new().dim_completed_lines(false), &mut state, 8, 3);
+        assert_style(&buf, 0, 0, widget.style_correct);
+        assert_style(&buf, 4, 0, widget.style_correct);
+        assert_style(&buf, 0, 1, widget.style_correct.patch(widget.style_active_word));
+    }
+}
@@ -0,0 +1,655 @@
+//! Word lists and generating the words for a test.
+
+use std::{collections::HashSet, fs, path::Path, sync::OnceLock};
+
+use rand::{distributions::uniform::SampleRange, prelude::*, rngs::SmallRng};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
+
+use crate::error::{CrabtypeError, Result};
+
+/// A list of words in the same json format as `words/english_1k.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WordList {
+    pub name: String,
+    pub words: Vec<String>,
+    /// BCP 47 language tag, e.g. `en` or `pt-BR`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// the words that start a new line, only texts have them
+    #[serde(skip)]
+    pub line_breaks: Vec<LineBreak>,
+    /// where the less common words start, for lists ordered from the most common word
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<DifficultyTiers>,
+}
+
+/// Indices of the first uncommon and the first rare word of a [`WordList`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DifficultyTiers {
+    pub uncommon: usize,
+    pub rare: usize,
+}
+
+/// Which words of a list with [`DifficultyTiers`] to type.
+#[derive(Serialize, Deserialize, EnumString, Display, clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Difficulty {
+    /// the common words
+    Easy,
+    /// the uncommon words
+    Medium,
+    /// the rare words
+    Hard,
+}
+
+/// A line of a text starting at `word`, after `indent` columns of indentation.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineBreak {
+    pub word: usize,
+    pub indent: usize,
+}
+
+/// Whether `c` can be typed as a letter of a word: any letter, digit or symbol
+/// of any script, including accents and the zero width joiners of emoji, but
+/// no control characters and no zero width spaces, which can't be seen.
+/// Spaces end words, so they aren't typed as letters either.
+pub fn is_typable(c: char) -> bool {
+    !c.is_control() && !c.is_whitespace() && !matches!(c, '\u{200b}' | '\u{2060}' | '\u{feff}')
+}
+
+/// Primary language subtags of the languages written right to left.
+const RTL_LANGUAGES: [&str; 7] = ["ar", "he", "fa", "ur", "yi", "ps", "sd"];
+
+/// Languages with bundled snippets for [`code_snippet`].
+pub const CODE_LANGUAGES: [&str; 3] = ["rust", "python", "javascript"];
+
+impl WordList {
+    /// Loads a word list from a json file, or the built-in english list when `path` is `None`.
+    pub fn load(path: Option<&str>) -> Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::built_in().clone());
+        };
+        let contents = fs::read_to_string(Path::new(path)).map_err(|source| CrabtypeError::ReadWordsFile {
+            path: path.to_string(),
+            source,
+        })?;
+        Self::from_json(&contents, path)
+    }
+
+    /// Parses a list in the format of a words file, `path` is where it came from.
+    pub(crate) fn from_json(json: &str, path: &str) -> Result<Self> {
+        let word_list: Self = serde_json::from_str(json).map_err(|source| CrabtypeError::ParseWordList {
+            path: path.to_string(),
+            source,
+        })?;
+        if word_list.words.is_empty() {
+            return Err(CrabtypeError::EmptyWordList(format!("words file '{path}'")));
+        }
+        Ok(word_list)
+    }
+
+    /// Writes the list to `path` in the format [`WordList::load`] reads.
+    pub fn save(&self, path: &str) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json + "\n").map_err(|source| CrabtypeError::WriteWordsFile {
+            path: path.to_string(),
+            source,
+        })
+    }
+
+    /// Adds the words of `other` that aren't in this list yet after its own
+    /// words, so with difficulty tiers they are among the rare words.
+    pub fn merge(&mut self, other: WordList) {
+        let mut seen: HashSet<String> = self.words.iter().cloned().collect();
+        self.words
+            .extend(other.words.into_iter().filter(|word| seen.insert(word.clone())));
+    }
+
+    /// Removes the word at `index`, the difficulty tiers after it move along.
+    pub fn remove_word(&mut self, index: usize) {
+        self.words.remove(index);
+        if let Some(tiers) = &mut self.difficulty {
+            for tier in [&mut tiers.uncommon, &mut tiers.rare] {
+                if *tier > index {
+                    *tier -= 1;
+                }
+            }
+        }
+    }
+
+    /// The built-in english list, only parsed the first time it's needed.
+    pub fn built_in() -> &'static Self {
+        static BUILT_IN: OnceLock<WordList> = OnceLock::new();
+        BUILT_IN.get_or_init(|| {
+            serde_json::from_str(include_str!("../words/english_1k.json"))
+                .expect("the built-in word list is valid")
+        })
+    }
+
+    /// The words of a text, to be typed in order on the same lines and with
+    /// the same indentation as in the text. A blank line between paragraphs is
+    /// a line break at the same word as the next line's.
+    pub fn from_text(name: &str, text: &str) -> Result<Self> {
+        let mut words = Vec::new();
+        let mut line_breaks = Vec::new();
+        for line in text.lines() {
+            if !words.is_empty() {
+                let indent = line
+                    .chars()
+                    .take_while(|c| c.is_whitespace())
+                    .map(|c| if c == '\t' { 4 } else { 1 })
+                    .sum();
+                line_breaks.push(LineBreak {
+                    word: words.len(),
+                    indent,
+                });
+            }
+            words.extend(line.split_whitespace().map(String::from));
+        }
+        // blank lines at the end have no words after them
+        while line_breaks.last().is_some_and(|line_break| line_break.word == words.len()) {
+            line_breaks.pop();
+        }
+        if words.is_empty() {
+            return Err(CrabtypeError::EmptyWordList(format!("the {name}")));
+        }
+        Ok(Self {
+            line_breaks,
+            ..Self::new(name, words)
+        })
+    }
+
+    /// A list of `words` without any metadata.
+    pub fn new(name: &str, words: Vec<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            words,
+            line_breaks: Vec::new(),
+            language: None,
+            author: None,
+            description: None,
+            difficulty: None,
+        }
+    }
+
+    /// Removes the characters that can't be typed, see [`is_typable`], like
+    /// zero width spaces that take no room on screen but would still have to be
+    /// typed, and words left empty by that. Returns the words that were changed,
+    /// as they were. The line breaks and difficulty tiers move along like with
+    /// [`WordList::remove_word`].
+    pub fn sanitize(&mut self) -> Vec<String> {
+        let mut changed = Vec::new();
+        // the index each word ends up at, or the next word's when it's removed
+        let mut new_index = Vec::with_capacity(self.words.len() + 1);
+        let mut kept = 0;
+        self.words.retain_mut(|word| {
+            new_index.push(kept);
+            if !word.chars().all(is_typable) {
+                changed.push(word.clone());
+                word.retain(is_typable);
+            }
+            kept += usize::from(!word.is_empty());
+            !word.is_empty()
+        });
+        new_index.push(kept);
+        let moved = |index: usize| new_index[index.min(new_index.len() - 1)];
+        // a line left without words stays as a blank line
+        for line_break in &mut self.line_breaks {
+            line_break.word = moved(line_break.word);
+        }
+        if let Some(tiers) = &mut self.difficulty {
+            tiers.uncommon = moved(tiers.uncommon);
+            tiers.rare = moved(tiers.rare);
+        }
+        changed
+    }
+
+    /// The name with the language when it's known, e.g. `english_1k (en)`.
+    pub fn title(&self) -> String {
+        match &self.language {
+            Some(language) => format!("{} ({language})", self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Whether the language of the list is written right to left.
+    pub fn is_rtl(&self) -> bool {
+        let Some(language) = &self.language else {
+            return false;
+        };
+        let primary = language.split(['-', '_']).next().unwrap_or_default();
+        RTL_LANGUAGES.iter().any(|rtl| rtl.eq_ignore_ascii_case(primary))
+    }
+
+    /// Keeps only the words of one difficulty, or all of them when the list has no tiers.
+    pub fn filter_difficulty(&mut self, difficulty: Difficulty) {
+        let Some(tiers) = self.difficulty else {
+            return;
+        };
+        let len = self.words.len();
+        let range = match difficulty {
+            Difficulty::Easy => 0..tiers.uncommon.min(len),
+            Difficulty::Medium => tiers.uncommon.min(len)..tiers.rare.min(len),
+            Difficulty::Hard => tiers.rare.min(len)..len,
+        };
+        // a tier without words would leave nothing to type
+        if !range.is_empty() {
+            self.words = self.words.drain(range).collect();
+        }
+    }
+}
+
+/// A random bundled snippet of code, typed with its symbols, lines and indentation.
+pub fn code_snippet<R: Rng>(language: &str, rng: &mut R) -> Result<WordList> {
+    let snippets = match language {
+        "rust" => include_str!("../snippets/rust.txt"),
+        "python" => include_str!("../snippets/python.txt"),
+        "javascript" => include_str!("../snippets/javascript.txt"),
+        _ => return Err(CrabtypeError::UnknownLanguage(language.to_string())),
+    };
+    // the snippets are separated by empty lines
+    let snippet = snippets.split("\n\n").choose(rng).unwrap_or_default();
+    WordList::from_text(language, snippet)
+}
+
+#[derive(EnumIter, Clone, Copy, PartialEq)]
+enum PunctuationKind {
+    Period,
+    Comma,
+    Hyphen,
+    Parantheses,
+    Exclamation,
+    Semicolon,
+    Colon,
+    DQuotes,
+    Quotes,
+}
+impl From<PunctuationKind> for char {
+    fn from(value: PunctuationKind) -> Self {
+        use PunctuationKind::*;
+        match value {
+            Period => '.',
+            Comma => ',',
+            Hyphen => '-',
+            Parantheses => ')',
+            Exclamation => '!',
+            Semicolon => ';',
+            Colon => ':',
+            DQuotes => '"',
+            Quotes => '\'',
+        }
+    }
+}
+
+/// Capitalizes the first word and sprinkles punctuation every `jump_range` words,
+/// capitalizing again after sentence endings.
+pub fn punctuate<R: Rng, S: SampleRange<usize> + Clone>(
+    words: Vec<String>,
+    jump_range: S,
+    rand: &mut R,
+) -> Vec<String> {
+    use PunctuationKind as PK;
+    let mut capitalize_next = true;
+    let mut new_words = Vec::new();
+
+    let punctuations: Vec<PK> = PK::iter().collect();
+    let weights = [3, 2, 2, 2, 2, 1, 2, 2, 2];
+    debug_assert_eq!(punctuations.len(), weights.len());
+
+    let dist = rand::distributions::WeightedIndex::new(weights).unwrap();
+
+    let mut next_index = rand.gen_range(jump_range.clone());
+
+    for (i, mut word) in words.into_iter().enumerate() {
+        if capitalize_next {
+            capitalize_next = false;
+            capitalize(&mut word);
+        }
+        if i == next_index {
+            next_index += rand.gen_range(jump_range.clone());
+            let pk = punctuations[dist.sample(rand)];
+            let c: char = pk.into();
+            match pk {
+                PK::Exclamation | PK::Period => {
+                    capitalize_next = true;
+                    word.push(c)
+                }
+                PK::DQuotes | PK::Quotes => word = format!("{c}{word}{c}"),
+                PK::Parantheses => word = format!("({word})"),
+                PK::Hyphen => new_words.push(c.into()),
+                _ => word.push(c),
+            }
+        }
+        new_words.push(word);
+    }
+    new_words
+}
+
+/// Uppercases the first letter of the word.
+pub fn capitalize(word: &mut String) {
+    let mut chars = word.chars();
+    if let Some(first) = chars.next() {
+        *word = first.to_uppercase().chain(chars).collect();
+    }
+}
+
+/// Capitalizes each word with the given probability, without adding any punctuation.
+pub fn capitalize_randomly<R: Rng>(words: Vec<String>, probability: f64, rand: &mut R) -> Vec<String> {
+    words
+        .into_iter()
+        .map(|mut word| {
+            if rand.gen_bool(probability) {
+                capitalize(&mut word);
+            }
+            word
+        })
+        .collect()
+}
+
+/// How the words of a test are picked from a word list, see [`generate_words`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WordOptions {
+    pub shuffle: bool,
+    pub capitalize: bool,
+    pub punctuate: bool,
+    pub difficulty: Option<Difficulty>,
+}
+
+/// The words of a test, always the same ones for the same `seed` and `options`.
+///
+/// The list is first filtered by difficulty, then every random draw is made
+/// from one rng seeded with `seed`, in this order: the shuffle, the
+/// capitalization of each word and the punctuation. Draws are only made for
+/// the enabled options, and new steps go after the existing ones so that a
+/// seed keeps giving the same words.
+pub fn generate_words(word_list: &WordList, seed: u64, options: &WordOptions) -> Vec<String> {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut word_list = word_list.clone();
+    if let Some(difficulty) = options.difficulty {
+        word_list.filter_difficulty(difficulty);
+    }
+    let mut words = word_list.words;
+    if options.shuffle {
+        words.shuffle(&mut rng);
+    }
+    if options.capitalize {
+        words = capitalize_randomly(words, 0.25, &mut rng);
+    }
+    if options.punctuate {
+        words = punctuate(words, 2..=4, &mut rng);
+    }
+    words
+}
+
+/// The seed of the daily challenge on `day`, in days since the unix epoch.
+/// It only depends on the day, so everyone gets the same words on the same day.
+pub fn daily_seed(day: u64) -> u64 {
+    // splitmix64, so days next to each other get unrelated seeds
+    let mut z = day.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// An endless supply of words for duration tests, the words are
+/// reshuffled every time they run out.
+pub struct WordPool {
+    words: Vec<String>,
+    rng: SmallRng,
+    shuffle: bool,
+}
+
+impl WordPool {
+    pub fn new(words: Vec<String>, rng: SmallRng, shuffle: bool) -> Self {
+        Self {
+            words,
+            rng,
+            shuffle,
+        }
+    }
+
+    /// Every word of the pool once.
+    pub fn next_batch(&mut self) -> Vec<String> {
+        let mut batch = self.words.clone();
+        if self.shuffle {
+            batch.shuffle(&mut self.rng);
+        }
+        batch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempPath;
+    #[test]
+    fn load_built_in() {
+        let word_list = WordList::load(None).unwrap();
+        assert_eq!(word_list.name, "english_1k");
+        assert!(!word_list.words.is_empty());
+        // parsed only once
+        assert!(std::ptr::eq(WordList::built_in(), WordList::built_in()));
+    }
+    #[test]
+    fn difficulty_tiers() {
+        let mut word_list = WordList::load(None).unwrap();
+        let len = word_list.words.len();
+        word_list.filter_difficulty(Difficulty::Easy);
+        assert_eq!(word_list.words.len(), 200);
+        assert_eq!(word_list.words[0], "the");
+
+        let mut word_list = WordList::load(None).unwrap();
+        word_list.filter_difficulty(Difficulty::Hard);
+        assert_eq!(word_list.words.len(), len - 600);
+        assert_eq!("hard".parse(), Ok(Difficulty::Hard));
+
+        // without tiers every word is kept
+        let mut text = WordList::from_text("text", "a b c").unwrap();
+        text.filter_difficulty(Difficulty::Hard);
+        assert_eq!(text.words, ["a", "b", "c"]);
+    }
+    #[test]
+    fn metadata() {
+        let old: WordList = serde_json::from_str(r#"{"name": "old", "words": ["a"]}"#).unwrap();
+        assert_eq!(old.title(), "old");
+        assert_eq!((old.language, old.author, old.description), (None, None, None));
+        let new: WordList = serde_json::from_str(
+            r#"{"name": "new", "words": ["a"], "language": "pt-BR", "author": "me", "description": "palavras"}"#,
+        )
+        .unwrap();
+        assert_eq!(new.title(), "new (pt-BR)");
+        assert_eq!(new.author.as_deref(), Some("me"));
+        assert_eq!(new.description.as_deref(), Some("palavras"));
+        assert!(!new.is_rtl());
+        let hebrew: WordList = serde_json::from_str(r#"{"name": "he", "words": ["שלום"], "language": "he-IL"}"#).unwrap();
+        assert!(hebrew.is_rtl());
+    }
+    #[test]
+    fn typable_chars() {
+        for c in ['a', 'Z', '~', 'é', 'ж', '中', '\u{301}', '\u{200d}', '🦀'] {
+            assert!(is_typable(c), "{c:?}");
+        }
+        for c in [' ', '\t', '\n', '\u{7}', '\u{200b}', '\u{feff}'] {
+            assert!(!is_typable(c), "{c:?}");
+        }
+    }
+    #[test]
+    fn sanitize_invisible_characters() {
+        let mut word_list: WordList = serde_json::from_str(
+            r#"{"name": "bad", "words": ["ok", "zero\u200bwidth", "\u0007", "fam\u200dily"]}"#,
+        )
+        .unwrap();
+        assert_eq!(word_list.sanitize(), ["zero\u{200b}width", "\u{7}"]);
+        assert_eq!(word_list.words, ["ok", "zerowidth", "fam\u{200d}ily"]);
+        assert!(word_list.sanitize().is_empty());
+
+        let mut text = WordList::from_text("text", "a \u{feff}\nb").unwrap();
+        text.sanitize();
+        assert_eq!(text.words, ["a", "b"]);
+        assert_eq!(text.line_breaks, [LineBreak { word: 1, indent: 0 }]);
+
+        let mut tiered = WordList::new("tiered", ["a", "\u{200b}", "b", "\u{200b}", "c"].map(String::from).to_vec());
+        tiered.difficulty = Some(DifficultyTiers { uncommon: 2, rare: 3 });
+        tiered.sanitize();
+        assert_eq!(tiered.words, ["a", "b", "c"]);
+        assert_eq!(tiered.difficulty, Some(DifficultyTiers { uncommon: 1, rare: 2 }));
+    }
+    #[test]
+    fn generated_words() {
+        let word_list = WordList::from_text("text", "one two three four five six").unwrap();
+        let options = WordOptions {
+            shuffle: true,
+            capitalize: true,
+            punctuate: true,
+            difficulty: None,
+        };
+        // changing this means that old seeds give different words
+        let words = generate_words(&word_list, 42, &options);
+        assert_eq!(words, ["Six", "three", "four,", "one", "two", "(five)"]);
+        assert_eq!(generate_words(&word_list, 42, &options), words);
+        assert_ne!(generate_words(&word_list, 43, &options), words);
+        assert_eq!(generate_words(&word_list, 42, &WordOptions::default()), word_list.words);
+
+        let mut word_list = WordList::load(None).unwrap();
+        let options = WordOptions {
+            difficulty: Some(Difficulty::Easy),
+            ..Default::default()
+        };
+        let words = generate_words(&word_list, 0, &options);
+        word_list.filter_difficulty(Difficulty::Easy);
+        assert_eq!(words, word_list.words);
+    }
+    #[test]
+    fn save_and_remove_words() {
+        let path = TempPath::new("words.json");
+        let path = path.to_str().unwrap();
+        let mut word_list: WordList = serde_json::from_str(
+            r#"{"name": "tiers", "words": ["a", "b", "c", "d"], "difficulty": {"uncommon": 1, "rare": 3}}"#,
+        )
+        .unwrap();
+        word_list.remove_word(1);
+        assert_eq!(word_list.difficulty, Some(DifficultyTiers { uncommon: 1, rare: 2 }));
+        word_list.save(path).unwrap();
+        let saved = fs::read_to_string(path).unwrap();
+        let loaded = WordList::load(Some(path)).unwrap();
+        assert!(!saved.contains("language"));
+        assert_eq!(loaded.words, ["a", "c", "d"]);
+        assert_eq!(loaded.difficulty, word_list.difficulty);
+    }
+    #[test]
+    fn merge_extra_words() {
+        let mut word_list = WordList::load(None).unwrap();
+        let len = word_list.words.len();
+        let extra = WordList::new("jargon", ["borrowck", "the", "monad", "borrowck"].map(String::from).to_vec());
+        word_list.merge(extra);
+        assert_eq!(word_list.words.len(), len + 2);
+        assert_eq!(word_list.words[..len], WordList::built_in().words[..]);
+        assert_eq!(word_list.words[len..], ["borrowck", "monad"]);
+        assert_eq!(word_list.name, "english_1k");
+    }
+    #[test]
+    fn load_missing_file() {
+        let err = WordList::load(Some("no/such/words.json")).unwrap_err();
+        assert!(matches!(err, CrabtypeError::ReadWordsFile { .. }));
+        assert!(err.to_string().starts_with("could not read words file 'no/such/words.json': "));
+
+        let path = TempPath::new("bad-words.json");
+        fs::write(&path, "{\"name\": \"bad\"").unwrap();
+        let err = WordList::load(path.to_str()).unwrap_err();
+        assert!(matches!(err, CrabtypeError::ParseWordList { .. }));
+        assert!(err.to_string().starts_with("could not parse words file"));
+
+        fs::write(&path, r#"{"name": "empty", "words": []}"#).unwrap();
+        let err = WordList::load(path.to_str()).unwrap_err();
+        assert_eq!(err.to_string(), format!("there are no words in words file '{}'", path.display()));
+    }
+    #[test]
+    fn words_from_text() {
+        let list = WordList::from_text("clipboard", "  Hello, world!\n\tfoo ").unwrap();
+        assert_eq!(list.words, ["Hello,", "world!", "foo"]);
+        assert_eq!(list.line_breaks, [LineBreak { word: 2, indent: 4 }]);
+        let poem = WordList::from_text("text", "\nroses are red\n\nviolets\n  are blue\n\n").unwrap();
+        assert_eq!(
+            poem.line_breaks,
+            [
+                LineBreak { word: 3, indent: 0 },
+                LineBreak { word: 3, indent: 0 },
+                LineBreak { word: 4, indent: 2 }
+            ]
+        );
+    }
+    #[test]
+    fn code_snippets() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        for language in CODE_LANGUAGES {
+            let snippet = code_snippet(language, &mut rng).unwrap();
+            assert_eq!(snippet.name, language);
+            assert!(!snippet.words.is_empty());
+            assert!((0..20).any(|_| {
+                let snippet = code_snippet(language, &mut rng).unwrap();
+                snippet.line_breaks.iter().any(|line_break| line_break.indent > 0)
+            }));
+        }
+        assert!(matches!(code_snippet("cobol", &mut rng), Err(CrabtypeError::UnknownLanguage(_))));
+        assert!(matches!(WordList::from_text("clipboard", " \n "), Err(CrabtypeError::EmptyWordList(_))));
+    }
+    #[test]
+    fn punctuate_capitalizes_first() {
+        let words = ["hello", "world"].map(String::from).to_vec();
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+        let words = punctuate(words, 5..=6, &mut rng);
+        assert_eq!(words, ["Hello", "world"]);
+    }
+    #[test]
+    fn word_pool_batches() {
+        let words = ["a", "b", "c"].map(String::from).to_vec();
+        let mut pool = WordPool::new(words.clone(), SmallRng::seed_from_u64(1), false);
+        assert_eq!(pool.next_batch(), words);
+        assert_eq!(pool.next_batch(), words);
+
+        let batches = |seed| {
+            let mut pool = WordPool::new(words.clone(), SmallRng::seed_from_u64(seed), true);
+            [pool.next_batch(), pool.next_batch()]
+        };
+        assert_eq!(batches(7), batches(7));
+        for mut batch in batches(7) {
+            batch.sort();
+            assert_eq!(batch, words);
+        }
+    }
+    #[test]
+    fn daily_seeds() {
+        assert_eq!(daily_seed(20_000), daily_seed(20_000));
+        assert_ne!(daily_seed(20_000), daily_seed(20_001));
+        // changing it would change the challenge of the day for some players but not others
+        assert_eq!(daily_seed(0), 0xe220a8397b1dcdaf);
+    }
+    #[test]
+    fn capitalize_first_letter() {
+        let mut word = String::from("élan");
+        capitalize(&mut word);
+        assert_eq!(word, "Élan");
+        let mut word = String::new();
+        capitalize(&mut word);
+        assert_eq!(word, "");
+    }
+    #[test]
+    fn capitalize_randomly_is_seeded() {
+        let words = WordList::load(None).unwrap().words;
+        let capitalized = |seed| {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            capitalize_randomly(words.clone(), 0.25, &mut rng)
+        };
+        assert_eq!(capitalized(3), capitalized(3));
+        let result = capitalized(3);
+        let changed = words.iter().zip(&result).filter(|(l, r)| l != r).count();
+        assert!(changed > 0 && changed < words.len());
+        assert!(result.iter().all(|w| w.chars().all(|c| c.is_alphanumeric() || c == '\'' || c == '-')));
+    }
+}